@@ -0,0 +1,44 @@
+// Fill the PUBLISHED_SHA256 table in src-tauri/src/whisper/catalog.rs with
+// the SHA-256 Hugging Face lists for every built-in model.
+// Usage: node scripts/model-hashes.mjs
+
+import { readFileSync, writeFileSync } from 'node:fs';
+
+const CATALOG = new URL('../src-tauri/src/whisper/catalog.rs', import.meta.url);
+const TREE_URL = 'https://huggingface.co/api/models/ggerganov/whisper.cpp/tree/main';
+
+async function listFiles() {
+  const files = [];
+  let url = TREE_URL;
+  while (url) {
+    const response = await fetch(url);
+    if (!response.ok) {
+      throw new Error(`Failed to list ${url}: ${response.status}`);
+    }
+    files.push(...(await response.json()));
+    url = response.headers.get('link')?.match(/<([^>]+)>;\s*rel="next"/)?.[1];
+  }
+  return files;
+}
+
+const source = readFileSync(CATALOG, 'utf8');
+const ids = [...source.matchAll(/spec\("([^"]+)"/g)].map((match) => match[1]);
+const hashes = new Map(
+  (await listFiles())
+    .filter((file) => file.lfs?.oid)
+    .map((file) => [file.path, file.lfs.oid]),
+);
+
+const missing = ids.filter((id) => !hashes.has(`ggml-${id}.bin`));
+if (missing.length > 0) {
+  throw new Error(`No published hash for ${missing.join(', ')}`);
+}
+
+const rows = ids.map((id) => `    ("${id}", "${hashes.get(`ggml-${id}.bin`)}"),`);
+const table = `const PUBLISHED_SHA256: &[(&str, &str)] = &[\n${rows.join('\n')}\n];`;
+const updated = source.replace(/const PUBLISHED_SHA256: &\[\(&str, &str\)\] = &\[[^;]*\];/, table);
+if (updated === source && !source.includes(table)) {
+  throw new Error('PUBLISHED_SHA256 table not found');
+}
+writeFileSync(CATALOG, updated);
+console.log(`Pinned ${ids.length} model hashes`);
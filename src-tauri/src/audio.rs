@@ -0,0 +1,366 @@
+// Audio conversion shared by file transcription and live capture
+// Everything is converted to what Whisper expects: 16kHz mono f32.
+
+#![cfg_attr(not(feature = "native-whisper"), allow(dead_code))]
+
+use std::f64::consts::PI;
+
+#[cfg(feature = "native-whisper")]
+mod decode;
+#[cfg(feature = "native-whisper")]
+pub use decode::{decode_file, read_wav};
+
+/// Sample rate Whisper models are trained on
+pub const WHISPER_SAMPLE_RATE: u32 = 16000;
+
+/// Zero crossings of the sinc kernel on each side of its centre.
+/// More gives a steeper anti-aliasing filter at a higher cost per sample.
+const ZERO_CROSSINGS: usize = 16;
+
+/// Passband as a fraction of the lower Nyquist frequency; the rest is the
+/// transition band of the anti-aliasing filter
+const ROLLOFF: f64 = 0.94;
+
+/// Pole of the DC blocking filter, closer to 1 keeps more low end
+const DC_POLE: f32 = 0.995;
+
+/// Average interleaved frames of `channels` samples into mono
+pub fn downmix(interleaved: &[f32], channels: usize) -> Vec<f32> {
+    if channels <= 1 {
+        return interleaved.to_vec();
+    }
+
+    interleaved
+        .chunks(channels)
+        .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+        .collect()
+}
+
+/// One-pole high-pass filter that removes any constant offset
+#[derive(Default)]
+pub struct DcBlocker {
+    prev_in: f32,
+    prev_out: f32,
+}
+
+impl DcBlocker {
+    pub fn process(&mut self, samples: &mut [f32]) {
+        for sample in samples.iter_mut() {
+            let out = *sample - self.prev_in + DC_POLE * self.prev_out;
+            self.prev_in = *sample;
+            self.prev_out = out;
+            *sample = out;
+        }
+    }
+}
+
+/// Remove any constant offset from a complete signal
+pub fn remove_dc(samples: &mut [f32]) {
+    DcBlocker::default().process(samples);
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-12 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+fn blackman(n: usize, len: usize) -> f64 {
+    let x = 2.0 * PI * n as f64 / (len - 1) as f64;
+    0.42 - 0.5 * x.cos() + 0.08 * (2.0 * x).cos()
+}
+
+/// Polyphase windowed-sinc resampler for a fixed rational ratio
+pub struct Resampler {
+    /// Upsampling factor
+    up: usize,
+    /// Downsampling factor
+    down: usize,
+    /// Low-pass prototype filter at the upsampled rate
+    filter: Vec<f32>,
+}
+
+impl Resampler {
+    pub fn new(from_rate: u32, to_rate: u32) -> Self {
+        let g = gcd(from_rate, to_rate).max(1);
+        let up = (to_rate / g) as usize;
+        let down = (from_rate / g) as usize;
+
+        // Cut off at the lower of the two Nyquist frequencies, expressed in
+        // cycles per sample at the upsampled rate
+        let factor = up.max(down);
+        let cutoff = ROLLOFF * 0.5 / factor as f64;
+        let len = 2 * ZERO_CROSSINGS * factor + 1;
+        let centre = (len / 2) as f64;
+
+        // Gain of `up` compensates for the zeros inserted when upsampling
+        let filter = (0..len)
+            .map(|n| {
+                let t = n as f64 - centre;
+                (up as f64 * 2.0 * cutoff * sinc(2.0 * cutoff * t) * blackman(n, len)) as f32
+            })
+            .collect();
+
+        Self { up, down, filter }
+    }
+
+    /// Position of output sample `m` on the upsampled time axis
+    fn position(&self, m: u64) -> i64 {
+        (m * self.down as u64) as i64 + (self.filter.len() / 2) as i64
+    }
+
+    /// First input sample that output `m` depends on
+    fn first_input(&self, m: u64) -> u64 {
+        let lowest = (self.position(m) - (self.filter.len() as i64 - 1)).max(0);
+        (lowest as u64).div_ceil(self.up as u64)
+    }
+
+    /// Last input sample that output `m` depends on
+    fn last_input(&self, m: u64) -> u64 {
+        self.position(m) as u64 / self.up as u64
+    }
+
+    /// Output sample `m`, where `input[0]` is input sample `base` and
+    /// samples past the end of `input` count as silence
+    fn output(&self, m: u64, input: &[f32], base: u64) -> f32 {
+        let t = self.position(m);
+        let first = self.first_input(m).max(base);
+        let end = (self.last_input(m) + 1).min(base + input.len() as u64);
+
+        (first..end)
+            .map(|i| {
+                let tap = (t - (i * self.up as u64) as i64) as usize;
+                input[(i - base) as usize] * self.filter[tap]
+            })
+            .sum()
+    }
+
+    /// Number of output samples for `input_len` input samples
+    fn output_len(&self, input_len: u64) -> u64 {
+        (input_len * self.up as u64).div_ceil(self.down as u64)
+    }
+
+    pub fn process(&self, input: &[f32]) -> Vec<f32> {
+        if self.up == self.down {
+            return input.to_vec();
+        }
+
+        (0..self.output_len(input.len() as u64))
+            .map(|m| self.output(m, input, 0))
+            .collect()
+    }
+}
+
+/// Incremental version of `prepare_for_whisper` for audio that arrives in
+/// pieces, such as a microphone. The output matches converting the whole
+/// recording at once.
+pub struct StreamConverter {
+    channels: usize,
+    resampler: Resampler,
+    dc: DcBlocker,
+    /// Mono input still needed by upcoming outputs
+    buffer: Vec<f32>,
+    /// Input sample index of `buffer[0]`
+    base: u64,
+    /// Input samples received so far
+    received: u64,
+    /// Index of the next output sample
+    next_output: u64,
+}
+
+impl StreamConverter {
+    pub fn new(channels: usize, sample_rate: u32) -> Self {
+        Self {
+            channels: channels.max(1),
+            resampler: Resampler::new(sample_rate, WHISPER_SAMPLE_RATE),
+            dc: DcBlocker::default(),
+            buffer: Vec::new(),
+            base: 0,
+            received: 0,
+            next_output: 0,
+        }
+    }
+
+    /// Convert a block of interleaved samples, returning the 16kHz output
+    /// that can be computed so far
+    pub fn push(&mut self, interleaved: &[f32]) -> Vec<f32> {
+        let mut mono = downmix(interleaved, self.channels);
+        self.dc.process(&mut mono);
+        self.received += mono.len() as u64;
+
+        if self.resampler.up == self.resampler.down {
+            self.next_output = self.received;
+            return mono;
+        }
+
+        self.buffer.extend(mono);
+        let mut output = Vec::new();
+        while self.resampler.last_input(self.next_output) < self.received {
+            output.push(
+                self.resampler
+                    .output(self.next_output, &self.buffer, self.base),
+            );
+            self.next_output += 1;
+        }
+
+        // Drop input that no upcoming output depends on
+        let keep_from = self.resampler.first_input(self.next_output).max(self.base);
+        self.buffer
+            .drain(..((keep_from - self.base) as usize).min(self.buffer.len()));
+        self.base = keep_from;
+
+        output
+    }
+
+    /// Flush the samples held back for the filter's look-ahead
+    pub fn finish(&mut self) -> Vec<f32> {
+        let total = self.resampler.output_len(self.received);
+        let output = (self.next_output..total)
+            .map(|m| self.resampler.output(m, &self.buffer, self.base))
+            .collect();
+        self.next_output = total;
+        output
+    }
+}
+
+/// Resample mono audio between two rates
+pub fn resample(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+    if from_rate == to_rate || samples.is_empty() {
+        return samples.to_vec();
+    }
+    Resampler::new(from_rate, to_rate).process(samples)
+}
+
+/// Convert interleaved audio of any channel count and rate into the 16kHz
+/// mono signal Whisper expects. Channels are mixed down before resampling so
+/// frames are never split.
+pub fn prepare_for_whisper(interleaved: &[f32], channels: usize, sample_rate: u32) -> Vec<f32> {
+    let mut mono = downmix(interleaved, channels);
+    remove_dc(&mut mono);
+    resample(&mono, sample_rate, WHISPER_SAMPLE_RATE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(freq: f32, rate: u32, seconds: f32, amplitude: f32) -> Vec<f32> {
+        let n = (rate as f32 * seconds) as usize;
+        (0..n)
+            .map(|i| amplitude * (2.0 * std::f32::consts::PI * freq * i as f32 / rate as f32).sin())
+            .collect()
+    }
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    /// Largest difference from an ideal sine, ignoring the filter's edge effects
+    fn max_error(actual: &[f32], expected: &[f32]) -> f32 {
+        let margin = 200;
+        let end = actual.len().min(expected.len()) - margin;
+        actual[margin..end]
+            .iter()
+            .zip(&expected[margin..end])
+            .map(|(a, e)| (a - e).abs())
+            .fold(0.0, f32::max)
+    }
+
+    #[test]
+    fn downsamples_integer_ratio() {
+        let input = sine(440.0, 48000, 1.0, 0.5);
+        let output = resample(&input, 48000, 16000);
+        assert_eq!(output.len(), 16000);
+        assert!(max_error(&output, &sine(440.0, 16000, 1.0, 0.5)) < 0.01);
+    }
+
+    #[test]
+    fn downsamples_fractional_ratio() {
+        let input = sine(1000.0, 44100, 1.0, 0.5);
+        let output = resample(&input, 44100, 16000);
+        assert_eq!(output.len(), 16000);
+        assert!(max_error(&output, &sine(1000.0, 16000, 1.0, 0.5)) < 0.01);
+    }
+
+    #[test]
+    fn upsamples() {
+        let input = sine(300.0, 8000, 1.0, 0.5);
+        let output = resample(&input, 8000, 16000);
+        assert_eq!(output.len(), 16000);
+        assert!(max_error(&output, &sine(300.0, 16000, 1.0, 0.5)) < 0.01);
+    }
+
+    #[test]
+    fn suppresses_frequencies_above_target_nyquist() {
+        // 12kHz cannot be represented at 16kHz and must not alias down to 4kHz
+        let input = sine(12000.0, 48000, 1.0, 0.5);
+        let output = resample(&input, 48000, 16000);
+        assert!(rms(&output[200..output.len() - 200]) < 0.005);
+    }
+
+    #[test]
+    fn same_rate_is_passthrough() {
+        let input = sine(440.0, 16000, 0.1, 0.5);
+        assert_eq!(resample(&input, 16000, 16000), input);
+    }
+
+    #[test]
+    fn downmixes_interleaved_frames() {
+        let stereo: Vec<f32> = (0..100).flat_map(|_| [0.5, 0.25]).collect();
+        let mono = downmix(&stereo, 2);
+        assert_eq!(mono.len(), 100);
+        assert!(mono.iter().all(|s| (s - 0.375).abs() < 1e-6));
+    }
+
+    #[test]
+    fn downmixes_before_resampling() {
+        // Opposite-phase channels cancel out; resampling first would mix
+        // samples from different frames and leave a residual
+        let left = sine(440.0, 48000, 0.5, 0.5);
+        let stereo: Vec<f32> = left.iter().flat_map(|&s| [s, -s]).collect();
+        let output = prepare_for_whisper(&stereo, 2, 48000);
+        assert_eq!(output.len(), 8000);
+        assert!(rms(&output) < 1e-4);
+    }
+
+    #[test]
+    fn streaming_matches_batch_conversion() {
+        let left = sine(440.0, 44100, 1.0, 0.5);
+        let stereo: Vec<f32> = left.iter().flat_map(|&s| [s + 0.1, s * 0.5]).collect();
+        let batch = prepare_for_whisper(&stereo, 2, 44100);
+
+        let mut converter = StreamConverter::new(2, 44100);
+        let mut streamed = Vec::new();
+        // Uneven block sizes, always whole frames
+        for block in stereo.chunks(2 * 441 + 34) {
+            streamed.extend(converter.push(block));
+        }
+        streamed.extend(converter.finish());
+
+        assert_eq!(streamed.len(), batch.len());
+        assert!(max_error(&streamed, &batch) < 1e-5);
+    }
+
+    #[test]
+    fn removes_dc_offset() {
+        let mut samples: Vec<f32> = sine(440.0, 16000, 2.0, 0.3)
+            .iter()
+            .map(|s| s + 0.4)
+            .collect();
+        remove_dc(&mut samples);
+        let tail = &samples[16000..];
+        let mean = tail.iter().sum::<f32>() / tail.len() as f32;
+        assert!(mean.abs() < 0.01);
+        assert!((rms(tail) - 0.3 / 2f32.sqrt()).abs() < 0.02);
+    }
+}
@@ -0,0 +1,435 @@
+// Audio file decoding
+// WAV is always read with hound. FLAC, MP3, Ogg Vorbis and M4A (AAC/ALAC)
+// need the `audio-formats` feature, Ogg Opus needs the `opus` feature.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// Interleaved samples as stored in the file, scaled to [-1, 1]
+pub struct DecodedAudio {
+    pub samples: Vec<f32>,
+    pub channels: usize,
+    pub sample_rate: u32,
+}
+
+enum Container {
+    Wav,
+    OggOpus,
+    Other,
+}
+
+/// Decode an audio file into the 16kHz mono signal Whisper expects
+pub fn decode_file(path: &Path) -> Result<Vec<f32>, String> {
+    let audio = match sniff(path)? {
+        Container::Wav => match decode_wav(path) {
+            // hound only reads PCM and float; symphonia also knows A-law,
+            // mu-law and ADPCM
+            Err(hound::Error::Unsupported) if cfg!(feature = "audio-formats") => {
+                decode_compressed(path)
+            }
+            result => result.map_err(|e| format!("Failed to read WAV file: {}", e)),
+        },
+        Container::OggOpus => decode_opus(path),
+        Container::Other => decode_compressed(path),
+    }?;
+
+    if audio.samples.is_empty() || audio.channels == 0 || audio.sample_rate == 0 {
+        return Err(format!("{} contains no audio", path.display()));
+    }
+
+    let mut samples = super::prepare_for_whisper(&audio.samples, audio.channels, audio.sample_rate);
+    for sample in samples.iter_mut() {
+        *sample = sample.clamp(-1.0, 1.0);
+    }
+    Ok(samples)
+}
+
+/// Read a WAV file without converting it
+pub fn read_wav(path: &Path) -> Result<DecodedAudio, String> {
+    decode_wav(path).map_err(|e| format!("Failed to read WAV file: {}", e))
+}
+
+/// Tell containers apart by their magic bytes rather than the file extension
+fn sniff(path: &Path) -> Result<Container, String> {
+    let mut header = [0u8; 64];
+    let mut file = File::open(path).map_err(|e| format!("Failed to open audio file: {}", e))?;
+    let len = file
+        .read(&mut header)
+        .map_err(|e| format!("Failed to read audio file: {}", e))?;
+    let header = &header[..len];
+
+    if header.len() >= 12
+        && (header.starts_with(b"RIFF") || header.starts_with(b"RF64"))
+        && &header[8..12] == b"WAVE"
+    {
+        Ok(Container::Wav)
+    } else if header.starts_with(b"OggS") && header.windows(8).any(|w| w == b"OpusHead") {
+        Ok(Container::OggOpus)
+    } else {
+        Ok(Container::Other)
+    }
+}
+
+fn decode_wav(path: &Path) -> Result<DecodedAudio, hound::Error> {
+    let mut reader = hound::WavReader::open(path)?;
+    let spec = reader.spec();
+
+    let samples = match spec.sample_format {
+        hound::SampleFormat::Float if spec.bits_per_sample == 32 => {
+            reader.samples::<f32>().collect::<Result<Vec<_>, _>>()?
+        }
+        hound::SampleFormat::Float => return Err(hound::Error::Unsupported),
+        hound::SampleFormat::Int => {
+            // 8-bit WAV is unsigned; hound already re-centres it around zero
+            let scale = (1u64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .map(|s| s.map(|s| s as f32 / scale))
+                .collect::<Result<Vec<_>, _>>()?
+        }
+    };
+
+    Ok(DecodedAudio {
+        samples,
+        channels: spec.channels as usize,
+        sample_rate: spec.sample_rate,
+    })
+}
+
+#[cfg(feature = "audio-formats")]
+fn decode_compressed(path: &Path) -> Result<DecodedAudio, String> {
+    use symphonia::core::audio::SampleBuffer;
+    use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL, CODEC_TYPE_OPUS};
+    use symphonia::core::errors::Error;
+    use symphonia::core::formats::FormatOptions;
+    use symphonia::core::io::MediaSourceStream;
+    use symphonia::core::meta::MetadataOptions;
+    use symphonia::core::probe::Hint;
+
+    let file = File::open(path).map_err(|e| format!("Failed to open audio file: {}", e))?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+
+    let mut hint = Hint::new();
+    if let Some(extension) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(extension);
+    }
+
+    let probed = symphonia::default::get_probe()
+        .format(
+            &hint,
+            stream,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .map_err(|e| match e {
+            Error::Unsupported(_) => {
+                format!("{} is not in a supported audio format", path.display())
+            }
+            e => format!("Failed to read audio file: {}", e),
+        })?;
+    let mut format = probed.format;
+
+    let track = format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or_else(|| format!("{} has no audio track", path.display()))?;
+    let track_id = track.id;
+    let codec = track.codec_params.codec;
+
+    if codec == CODEC_TYPE_OPUS {
+        return Err("Opus audio is only supported in Ogg files".to_string());
+    }
+
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|e| match e {
+            Error::Unsupported(_) => {
+                let name = symphonia::default::get_codecs()
+                    .get_codec(codec)
+                    .map(|d| d.long_name)
+                    .unwrap_or("this codec");
+                format!("Unsupported audio codec: {}", name)
+            }
+            e => format!("Failed to create audio decoder: {}", e),
+        })?;
+
+    let mut samples = Vec::new();
+    let mut channels = track.codec_params.channels.map(|c| c.count()).unwrap_or(0);
+    let mut sample_rate = track.codec_params.sample_rate.unwrap_or(0);
+    let mut buffer: Option<SampleBuffer<f32>> = None;
+
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(Error::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(format!("Failed to read audio file: {}", e)),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // A corrupt frame costs a few milliseconds of audio, keep going
+            Err(Error::DecodeError(_)) => continue,
+            Err(e) => return Err(format!("Failed to decode audio: {}", e)),
+        };
+
+        let spec = *decoded.spec();
+        channels = spec.channels.count();
+        sample_rate = spec.rate;
+
+        let buffer = match &mut buffer {
+            Some(buffer) if buffer.capacity() >= decoded.capacity() * channels => buffer,
+            buffer => buffer.insert(SampleBuffer::new(decoded.capacity() as u64, spec)),
+        };
+        buffer.copy_interleaved_ref(decoded);
+        samples.extend_from_slice(buffer.samples());
+    }
+
+    Ok(DecodedAudio {
+        samples,
+        channels,
+        sample_rate,
+    })
+}
+
+#[cfg(not(feature = "audio-formats"))]
+fn decode_compressed(path: &Path) -> Result<DecodedAudio, String> {
+    Err(format!(
+        "{} is not a WAV file. FLAC, MP3, Ogg and M4A support needs the `audio-formats` feature.",
+        path.display()
+    ))
+}
+
+#[cfg(feature = "opus")]
+fn decode_opus(path: &Path) -> Result<DecodedAudio, String> {
+    use audiopus::coder::Decoder;
+    use audiopus::{Channels, SampleRate};
+    use std::io::BufReader;
+
+    /// Opus always decodes at 48kHz
+    const OPUS_RATE: u32 = 48000;
+    /// Longest Opus frame is 120ms
+    const MAX_FRAME: usize = OPUS_RATE as usize * 120 / 1000;
+
+    let file = File::open(path).map_err(|e| format!("Failed to open audio file: {}", e))?;
+    let mut reader = ogg::reading::PacketReader::new(BufReader::new(file));
+    let read_error = |e: ogg::OggReadError| format!("Failed to read Ogg file: {}", e);
+
+    let head = reader
+        .read_packet()
+        .map_err(read_error)?
+        .filter(|p| p.data.starts_with(b"OpusHead") && p.data.len() >= 19)
+        .ok_or_else(|| format!("{} is not an Ogg Opus file", path.display()))?;
+    let serial = head.stream_serial();
+    let channel_count = head.data[9] as usize;
+    let pre_skip = u16::from_le_bytes([head.data[10], head.data[11]]) as usize;
+    let mapping_family = head.data[18];
+
+    let channels = match (mapping_family, channel_count) {
+        (0, 1) => Channels::Mono,
+        (0, 2) => Channels::Stereo,
+        _ => {
+            return Err(format!(
+                "Unsupported Opus channel layout ({} channels, mapping family {})",
+                channel_count, mapping_family
+            ))
+        }
+    };
+    let mut decoder = Decoder::new(SampleRate::Hz48000, channels)
+        .map_err(|e| format!("Failed to create Opus decoder: {}", e))?;
+
+    let mut samples = Vec::new();
+    let mut frame = vec![0.0f32; MAX_FRAME * channel_count];
+    let mut end_granule = None;
+    let mut seen_tags = false;
+
+    while let Some(packet) = reader.read_packet().map_err(read_error)? {
+        if packet.stream_serial() != serial {
+            continue;
+        }
+        // The comment header follows the ID header and carries no audio
+        if !seen_tags {
+            seen_tags = true;
+            continue;
+        }
+
+        let input = (&packet.data[..])
+            .try_into()
+            .map_err(|e| format!("Invalid Opus packet: {}", e))?;
+        let output = (&mut frame[..])
+            .try_into()
+            .map_err(|e| format!("Invalid Opus buffer: {}", e))?;
+        let decoded = decoder
+            .decode_float(Some(input), output, false)
+            .map_err(|e| format!("Failed to decode Opus audio: {}", e))?;
+        samples.extend_from_slice(&frame[..decoded * channel_count]);
+        end_granule = Some(packet.absgp_page());
+    }
+
+    // The granule position of the last page counts the real samples plus the
+    // encoder's priming; anything decoded beyond it is padding
+    let mut frames = samples.len() / channel_count;
+    if let Some(granule) = end_granule {
+        frames = frames.min(granule as usize);
+    }
+    samples.truncate(frames * channel_count);
+    samples.drain(..(pre_skip * channel_count).min(samples.len()));
+
+    Ok(DecodedAudio {
+        samples,
+        channels: channel_count,
+        sample_rate: OPUS_RATE,
+    })
+}
+
+#[cfg(not(feature = "opus"))]
+fn decode_opus(path: &Path) -> Result<DecodedAudio, String> {
+    Err(format!(
+        "{} is an Ogg Opus file. Opus support needs the `opus` feature.",
+        path.display()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("decode-{}-{}.wav", name, std::process::id()))
+    }
+
+    /// Write mono samples at 16kHz with hound and read them back unconverted
+    fn round_trip<S: hound::Sample + Copy>(
+        name: &str,
+        bits: u16,
+        format: hound::SampleFormat,
+        samples: &[S],
+    ) -> Vec<f32> {
+        let path = temp_path(name);
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 16000,
+            bits_per_sample: bits,
+            sample_format: format,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for &sample in samples {
+            writer.write_sample(sample).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let audio = read_wav(&path);
+        let _ = std::fs::remove_file(&path);
+        let audio = audio.unwrap();
+        assert_eq!((audio.channels, audio.sample_rate), (1, 16000));
+        audio.samples
+    }
+
+    fn assert_close(actual: &[f32], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!(
+                (a - e).abs() < 1e-4,
+                "got {:?}, expected {:?}",
+                actual,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn scales_8_bit_wav() {
+        let samples = round_trip("8bit", 8, hound::SampleFormat::Int, &[-128i8, 0, 64, 127]);
+        assert_close(&samples, &[-1.0, 0.0, 0.5, 127.0 / 128.0]);
+    }
+
+    #[test]
+    fn scales_16_bit_wav() {
+        let samples = round_trip(
+            "16bit",
+            16,
+            hound::SampleFormat::Int,
+            &[-32768i16, 0, 16384, 32767],
+        );
+        assert_close(&samples, &[-1.0, 0.0, 0.5, 32767.0 / 32768.0]);
+    }
+
+    #[test]
+    fn scales_24_bit_wav() {
+        let samples = round_trip(
+            "24bit",
+            24,
+            hound::SampleFormat::Int,
+            &[-8_388_608i32, 0, 4_194_304, -2_097_152],
+        );
+        assert_close(&samples, &[-1.0, 0.0, 0.5, -0.25]);
+    }
+
+    #[test]
+    fn keeps_float_wav_as_is() {
+        let input = [-1.0f32, 0.0, 0.5, -0.25];
+        let samples = round_trip("float", 32, hound::SampleFormat::Float, &input);
+        assert_close(&samples, &input);
+    }
+
+    /// One second of an 8kHz mu-law WAV, which hound can't read, holding a
+    /// 500Hz square wave at the largest mu-law amplitude
+    fn write_mulaw_wav(path: &Path) {
+        let data: Vec<u8> = (0..8000)
+            .map(|i| if i / 8 % 2 == 0 { 0x80 } else { 0x00 })
+            .collect();
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(4 + 26 + 8 + data.len() as u32).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&18u32.to_le_bytes());
+        wav.extend_from_slice(&7u16.to_le_bytes()); // WAVE_FORMAT_MULAW
+        wav.extend_from_slice(&1u16.to_le_bytes()); // channels
+        wav.extend_from_slice(&8000u32.to_le_bytes()); // sample rate
+        wav.extend_from_slice(&8000u32.to_le_bytes()); // bytes per second
+        wav.extend_from_slice(&1u16.to_le_bytes()); // block align
+        wav.extend_from_slice(&8u16.to_le_bytes()); // bits per sample
+        wav.extend_from_slice(&0u16.to_le_bytes()); // extension size
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        wav.extend_from_slice(&data);
+        std::fs::write(path, wav).unwrap();
+    }
+
+    #[cfg(feature = "audio-formats")]
+    #[test]
+    fn falls_back_to_symphonia_for_other_wav_codecs() {
+        let path = temp_path("mulaw");
+        write_mulaw_wav(&path);
+        let hound_result = read_wav(&path);
+        let audio = decode_file(&path);
+        let _ = std::fs::remove_file(&path);
+
+        assert!(hound_result.is_err());
+        let audio = audio.unwrap();
+        assert!(
+            (audio.len() as i64 - 16000).abs() <= 2,
+            "got {} samples",
+            audio.len()
+        );
+        let middle = &audio[4000..12000];
+        let rms = (middle.iter().map(|s| s * s).sum::<f32>() / middle.len() as f32).sqrt();
+        assert!(rms > 0.8, "rms {}", rms);
+    }
+
+    #[cfg(not(feature = "audio-formats"))]
+    #[test]
+    fn rejects_other_wav_codecs_without_audio_formats() {
+        let path = temp_path("mulaw");
+        write_mulaw_wav(&path);
+        let result = decode_file(&path);
+        let _ = std::fs::remove_file(&path);
+
+        assert!(result.unwrap_err().starts_with("Failed to read WAV file"));
+    }
+}
@@ -0,0 +1,163 @@
+// Native microphone capture
+// Audio is recorded in Rust, converted to 16kHz mono and streamed straight
+// into a native transcription session. Sources sit behind `AudioSource`, so a
+// WAV file can stand in for a microphone.
+
+#![cfg_attr(not(feature = "native-capture"), allow(dead_code))]
+
+#[cfg(feature = "native-capture")]
+mod device;
+#[cfg(feature = "native-whisper")]
+mod recorder;
+#[cfg(feature = "native-whisper")]
+mod wav;
+
+#[cfg(feature = "native-whisper")]
+pub use recorder::*;
+
+use serde::Serialize;
+use tauri::command;
+
+/// Device ids starting with this play a WAV file instead of a microphone
+pub const WAV_DEVICE_PREFIX: &str = "wav:";
+
+/// An audio input device
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceInfo {
+    pub id: String,
+    pub name: String,
+    pub is_default: bool,
+    pub channels: u16,
+    pub sample_rate: u32,
+}
+
+/// Receives interleaved samples from an `AudioSource`
+pub type DataCallback = Box<dyn FnMut(&[f32]) + Send>;
+
+/// Receives errors an `AudioSource` runs into after it has started
+pub type ErrorCallback = Box<dyn FnMut(String) + Send>;
+
+/// Something that produces audio, such as a microphone
+pub trait AudioSource: Send {
+    /// Channels per frame and frames per second of the delivered audio
+    fn format(&self) -> (usize, u32);
+
+    /// Start delivering interleaved samples to `on_data`. The source drops
+    /// `on_data` once it is stopped or runs out of audio. Errors while
+    /// running, such as a device being unplugged, go to `on_error`.
+    fn start(&mut self, on_data: DataCallback, on_error: ErrorCallback) -> Result<(), String>;
+
+    /// Stop delivering audio and wait for the last callback to return
+    fn stop(&mut self);
+}
+
+/// List audio input devices
+#[cfg(feature = "native-capture")]
+#[command]
+pub fn list_audio_devices() -> Result<Vec<DeviceInfo>, String> {
+    device::list_devices()
+}
+
+#[cfg(not(feature = "native-capture"))]
+#[command]
+pub fn list_audio_devices() -> Result<Vec<DeviceInfo>, String> {
+    Ok(Vec::new())
+}
+
+#[cfg(not(feature = "native-whisper"))]
+#[command]
+pub fn start_recording(
+    _device_id: Option<String>,
+    _options: Option<crate::whisper::TranscribeOptions>,
+) -> Result<u64, String> {
+    Err("Native Whisper not enabled. Using WASM fallback.".to_string())
+}
+
+#[cfg(not(feature = "native-whisper"))]
+#[command]
+pub fn stop_recording() -> Result<String, String> {
+    Err("Native Whisper not enabled. Using WASM fallback.".to_string())
+}
+
+#[cfg(all(test, feature = "native-whisper"))]
+mod tests {
+    use super::recorder::Recorder;
+    use super::wav::WavSource;
+    use std::path::PathBuf;
+    use std::sync::{Arc, Mutex};
+
+    fn write_stereo_sine(name: &str, seconds: f32) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("{}-{}.wav", name, std::process::id()));
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 48000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for i in 0..(48000.0 * seconds) as usize {
+            let t = i as f32 / 48000.0;
+            let sample = (0.5 * (2.0 * std::f32::consts::PI * 440.0 * t).sin() * 32767.0) as i16;
+            writer.write_sample(sample).unwrap();
+            writer.write_sample(sample).unwrap();
+        }
+        writer.finalize().unwrap();
+        path
+    }
+
+    fn record(source: WavSource) -> Vec<f32> {
+        let captured = Arc::new(Mutex::new(Vec::new()));
+        let sink = captured.clone();
+        let recorder = Recorder::start(
+            Box::new(source),
+            move |chunk| sink.lock().unwrap().extend(chunk),
+            |e| panic!("{}", e),
+        )
+        .unwrap();
+        recorder.stop();
+        let captured = captured.lock().unwrap().clone();
+        captured
+    }
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    #[test]
+    fn records_wav_device_as_16khz_mono() {
+        let path = write_stereo_sine("capture-batch", 1.0);
+        let captured = record(WavSource::open(&path, false).unwrap());
+        let _ = std::fs::remove_file(&path);
+
+        assert!(
+            (captured.len() as i64 - 16000).abs() <= 2,
+            "got {} samples",
+            captured.len()
+        );
+        let level = rms(&captured[4000..12000]);
+        assert!((level - 0.5 / 2f32.sqrt()).abs() < 0.02, "rms {}", level);
+    }
+
+    #[test]
+    fn stopping_ends_realtime_playback_early() {
+        let path = write_stereo_sine("capture-realtime", 2.0);
+        let source = WavSource::open(&path, true).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        let captured = Arc::new(Mutex::new(Vec::new()));
+        let sink = captured.clone();
+        let recorder = Recorder::start(
+            Box::new(source),
+            move |chunk| sink.lock().unwrap().extend(chunk),
+            |e| panic!("{}", e),
+        )
+        .unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        recorder.stop();
+
+        let captured = captured.lock().unwrap();
+        assert!(!captured.is_empty());
+        assert!(captured.len() < 32000, "got {} samples", captured.len());
+    }
+}
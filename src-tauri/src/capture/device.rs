@@ -0,0 +1,161 @@
+// Microphones through cpal
+// cpal streams can't move between threads on every platform, so each
+// stream lives on its own thread until the recording stops.
+
+use super::{AudioSource, DataCallback, DeviceInfo, ErrorCallback};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, SampleFormat, SizedSample};
+use std::sync::mpsc;
+use std::thread::JoinHandle;
+
+pub fn list_devices() -> Result<Vec<DeviceInfo>, String> {
+    let host = cpal::default_host();
+    let default_name = host.default_input_device().and_then(|d| d.name().ok());
+    let devices = host
+        .input_devices()
+        .map_err(|e| format!("Failed to list audio devices: {}", e))?;
+
+    Ok(devices
+        .filter_map(|device| {
+            let name = device.name().ok()?;
+            // Devices without a usable input configuration can't be recorded from
+            let config = device.default_input_config().ok()?;
+            Some(DeviceInfo {
+                id: name.clone(),
+                is_default: default_name.as_deref() == Some(name.as_str()),
+                name,
+                channels: config.channels(),
+                sample_rate: config.sample_rate().0,
+            })
+        })
+        .collect())
+}
+
+/// Find an input device by id, or the default input device
+fn find_device(device_id: Option<&str>) -> Result<cpal::Device, String> {
+    let host = cpal::default_host();
+    match device_id {
+        Some(id) => host
+            .input_devices()
+            .map_err(|e| format!("Failed to list audio devices: {}", e))?
+            .find(|d| d.name().map(|name| name == id).unwrap_or(false))
+            .ok_or_else(|| format!("Audio device not found: {}", id)),
+        None => host
+            .default_input_device()
+            .ok_or_else(|| "No audio input device available".to_string()),
+    }
+}
+
+fn build_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    mut on_data: DataCallback,
+    mut on_error: ErrorCallback,
+) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let mut buffer = Vec::new();
+    device.build_input_stream(
+        config,
+        move |data: &[T], _: &cpal::InputCallbackInfo| {
+            buffer.clear();
+            buffer.extend(data.iter().map(|&s| s.to_sample::<f32>()));
+            on_data(&buffer);
+        },
+        move |e| on_error(format!("Audio capture error: {}", e)),
+        None,
+    )
+}
+
+pub struct CpalSource {
+    device_id: Option<String>,
+    config: cpal::SupportedStreamConfig,
+    stop: Option<mpsc::Sender<()>>,
+    stream_thread: Option<JoinHandle<()>>,
+}
+
+impl CpalSource {
+    pub fn open(device_id: Option<&str>) -> Result<Self, String> {
+        let device = find_device(device_id)?;
+        let config = device
+            .default_input_config()
+            .map_err(|e| format!("Failed to get audio device configuration: {}", e))?;
+        Ok(Self {
+            device_id: device_id.map(str::to_string),
+            config,
+            stop: None,
+            stream_thread: None,
+        })
+    }
+}
+
+impl AudioSource for CpalSource {
+    fn format(&self) -> (usize, u32) {
+        (self.config.channels() as usize, self.config.sample_rate().0)
+    }
+
+    fn start(&mut self, on_data: DataCallback, on_error: ErrorCallback) -> Result<(), String> {
+        let device_id = self.device_id.clone();
+        let config = self.config.clone();
+        let (ready_tx, ready_rx) = mpsc::channel();
+        let (stop_tx, stop_rx) = mpsc::channel::<()>();
+
+        let stream_thread = std::thread::Builder::new()
+            .name("audio-device".to_string())
+            .spawn(move || {
+                let stream = find_device(device_id.as_deref()).and_then(|device| {
+                    let stream_config = config.config();
+                    let stream = match config.sample_format() {
+                        SampleFormat::F32 => {
+                            build_stream::<f32>(&device, &stream_config, on_data, on_error)
+                        }
+                        SampleFormat::I16 => {
+                            build_stream::<i16>(&device, &stream_config, on_data, on_error)
+                        }
+                        SampleFormat::U16 => {
+                            build_stream::<u16>(&device, &stream_config, on_data, on_error)
+                        }
+                        SampleFormat::I32 => {
+                            build_stream::<i32>(&device, &stream_config, on_data, on_error)
+                        }
+                        format => return Err(format!("Unsupported sample format: {}", format)),
+                    }
+                    .map_err(|e| format!("Failed to open audio device: {}", e))?;
+                    stream
+                        .play()
+                        .map_err(|e| format!("Failed to start audio device: {}", e))?;
+                    Ok(stream)
+                });
+
+                match stream {
+                    Ok(stream) => {
+                        let _ = ready_tx.send(Ok(()));
+                        // Wait for stop; dropping the stream releases the device
+                        let _ = stop_rx.recv();
+                        drop(stream);
+                    }
+                    Err(e) => {
+                        let _ = ready_tx.send(Err(e));
+                    }
+                }
+            })
+            .map_err(|e| format!("Failed to start audio capture: {}", e))?;
+
+        ready_rx
+            .recv()
+            .map_err(|_| "Audio device stopped unexpectedly".to_string())??;
+        self.stop = Some(stop_tx);
+        self.stream_thread = Some(stream_thread);
+        Ok(())
+    }
+
+    fn stop(&mut self) {
+        // Dropping the sender wakes the stream thread
+        self.stop.take();
+        if let Some(stream_thread) = self.stream_thread.take() {
+            let _ = stream_thread.join();
+        }
+    }
+}
@@ -0,0 +1,266 @@
+// Recording into a transcription stream
+// The source callback only copies samples into a channel; conversion to
+// 16kHz mono happens on a separate thread so the audio callback never blocks.
+
+use super::wav::WavSource;
+use super::{AudioSource, WAV_DEVICE_PREFIX};
+use crate::audio::StreamConverter;
+use crate::vad::{Vad, VadEvent};
+use crate::whisper::{self, TranscribeOptions};
+use std::path::Path;
+use std::sync::mpsc;
+use std::sync::Mutex;
+use std::thread::JoinHandle;
+use tauri::{command, AppHandle, Emitter, Runtime};
+
+/// An audio source feeding converted audio to a callback
+pub struct Recorder {
+    source: Box<dyn AudioSource>,
+    converter: Option<JoinHandle<()>>,
+}
+
+impl Recorder {
+    /// Start `source` and pass its audio to `on_audio` as 16kHz mono, and
+    /// errors the source runs into to `on_error`
+    pub fn start<F, E>(
+        mut source: Box<dyn AudioSource>,
+        mut on_audio: F,
+        on_error: E,
+    ) -> Result<Self, String>
+    where
+        F: FnMut(Vec<f32>) + Send + 'static,
+        E: FnMut(String) + Send + 'static,
+    {
+        let (channels, sample_rate) = source.format();
+        let (tx, rx) = mpsc::channel::<Vec<f32>>();
+
+        // Runs until the source drops its sender
+        let converter = std::thread::Builder::new()
+            .name("audio-capture".to_string())
+            .spawn(move || {
+                let mut converter = StreamConverter::new(channels, sample_rate);
+                for block in rx {
+                    let audio = converter.push(&block);
+                    if !audio.is_empty() {
+                        on_audio(audio);
+                    }
+                }
+                let rest = converter.finish();
+                if !rest.is_empty() {
+                    on_audio(rest);
+                }
+            })
+            .map_err(|e| format!("Failed to start audio capture: {}", e))?;
+
+        source.start(
+            Box::new(move |data: &[f32]| {
+                let _ = tx.send(data.to_vec());
+            }),
+            Box::new(on_error),
+        )?;
+
+        Ok(Self {
+            source,
+            converter: Some(converter),
+        })
+    }
+
+    /// Stop the source and wait until all captured audio has been delivered
+    pub fn stop(mut self) {
+        self.source.stop();
+        if let Some(converter) = self.converter.take() {
+            let _ = converter.join();
+        }
+    }
+}
+
+struct Recording {
+    recorder: Recorder,
+    session_id: u64,
+}
+
+static RECORDING: Mutex<Option<Recording>> = Mutex::new(None);
+
+fn open_source(device_id: Option<&str>) -> Result<Box<dyn AudioSource>, String> {
+    if let Some(path) = device_id.and_then(|id| id.strip_prefix(WAV_DEVICE_PREFIX)) {
+        return Ok(Box::new(WavSource::open(Path::new(path), true)?));
+    }
+    open_device(device_id)
+}
+
+#[cfg(feature = "native-capture")]
+fn open_device(device_id: Option<&str>) -> Result<Box<dyn AudioSource>, String> {
+    Ok(Box::new(super::device::CpalSource::open(device_id)?))
+}
+
+#[cfg(not(feature = "native-capture"))]
+fn open_device(_device_id: Option<&str>) -> Result<Box<dyn AudioSource>, String> {
+    Err("Native audio capture not enabled. Build with the `native-capture` feature.".to_string())
+}
+
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+enum StopReason {
+    Requested,
+    Silence,
+}
+
+/// Reports speech in the recorded audio and ends the recording after the
+/// configured silence
+struct SpeechMonitor<R: Runtime> {
+    app: AppHandle<R>,
+    session_id: u64,
+    vad: Vad,
+    silence_timeout_ms: u64,
+    timed_out: bool,
+}
+
+impl<R: Runtime> SpeechMonitor<R> {
+    fn process(&mut self, audio: &[f32]) {
+        for event in self.vad.push(audio) {
+            self.emit(event);
+        }
+
+        if self.silence_timeout_ms > 0
+            && !self.timed_out
+            && self.vad.silence_ms() >= self.silence_timeout_ms
+        {
+            self.timed_out = true;
+            let app = self.app.clone();
+            let session_id = self.session_id;
+            // Stopping waits for the thread this runs on, so it happens elsewhere
+            tauri::async_runtime::spawn(async move {
+                let _ = end_recording(&app, Some(session_id), StopReason::Silence).await;
+            });
+        }
+    }
+
+    fn emit(&self, event: VadEvent) {
+        let (name, at_ms) = match event {
+            VadEvent::SpeechStart { at_ms } => ("speech-start", at_ms),
+            VadEvent::SpeechEnd { at_ms } => ("speech-end", at_ms),
+        };
+        let _ = self.app.emit(
+            name,
+            serde_json::json!({
+                "sessionId": self.session_id,
+                "atMs": at_ms,
+            }),
+        );
+    }
+}
+
+impl<R: Runtime> Drop for SpeechMonitor<R> {
+    // Dropped once the recording has delivered its last audio; speech still
+    // going at that point ends with it
+    fn drop(&mut self) {
+        for event in self.vad.finish() {
+            self.emit(event);
+        }
+    }
+}
+
+/// Record from an input device (the default one if none is given) into a
+/// new transcription stream and return its session id. Emits `speech-start`
+/// and `speech-end` while recording, and `audio-capture-error` when the
+/// device reports a problem.
+#[command]
+pub fn start_recording<R: Runtime>(
+    app: AppHandle<R>,
+    device_id: Option<String>,
+    options: Option<TranscribeOptions>,
+) -> Result<u64, String> {
+    let mut recording = RECORDING.lock().map_err(|_| "Lock poisoned")?;
+    if recording.is_some() {
+        return Err("Already recording".to_string());
+    }
+
+    crate::focus::remember();
+    let vad = crate::settings::load()?.vad;
+    let source = open_source(device_id.as_deref())?;
+    let session_id = whisper::start_stream(app.clone(), options)?;
+
+    let mut monitor = SpeechMonitor {
+        app: app.clone(),
+        session_id,
+        vad: Vad::new(&vad),
+        silence_timeout_ms: vad.silence_timeout_ms as u64,
+        timed_out: false,
+    };
+    let error_app = app.clone();
+    let recorder = Recorder::start(
+        source,
+        move |audio| {
+            monitor.process(&audio);
+            let _ = whisper::push_audio(session_id, audio);
+        },
+        move |error| {
+            let _ = error_app.emit(
+                "audio-capture-error",
+                serde_json::json!({
+                    "sessionId": session_id,
+                    "error": error,
+                }),
+            );
+        },
+    );
+    let recorder = match recorder {
+        Ok(recorder) => recorder,
+        Err(e) => {
+            tauri::async_runtime::spawn(whisper::finish_stream(session_id));
+            return Err(e);
+        }
+    };
+
+    *recording = Some(Recording {
+        recorder,
+        session_id,
+    });
+    let _ = app.emit(
+        "recording-started",
+        serde_json::json!({
+            "sessionId": session_id,
+        }),
+    );
+    Ok(session_id)
+}
+
+/// Stop the recording, or only `session_id` when given, and return its
+/// transcript
+async fn end_recording<R: Runtime>(
+    app: &AppHandle<R>,
+    session_id: Option<u64>,
+    reason: StopReason,
+) -> Result<String, String> {
+    let recording = {
+        let mut current = RECORDING.lock().map_err(|_| "Lock poisoned")?;
+        match &*current {
+            Some(r) if session_id.is_none_or(|id| id == r.session_id) => current.take(),
+            _ => None,
+        }
+    }
+    .ok_or("Not recording")?;
+    let session_id = recording.session_id;
+
+    tauri::async_runtime::spawn_blocking(move || recording.recorder.stop())
+        .await
+        .map_err(|e| format!("Failed to stop recording: {}", e))?;
+    let text = whisper::finish_stream(session_id).await?;
+
+    let _ = app.emit(
+        "recording-stopped",
+        serde_json::json!({
+            "sessionId": session_id,
+            "text": text,
+            "reason": reason,
+        }),
+    );
+    Ok(text)
+}
+
+/// Stop recording and return the full transcript. Recordings also stop on
+/// their own after the configured silence, announced by `recording-stopped`.
+#[command]
+pub async fn stop_recording<R: Runtime>(app: AppHandle<R>) -> Result<String, String> {
+    end_recording(&app, None, StopReason::Requested).await
+}
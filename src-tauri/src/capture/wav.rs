@@ -0,0 +1,80 @@
+// WAV file posing as an audio device, for tests and trying capture without
+// a microphone
+
+use super::{AudioSource, DataCallback, ErrorCallback};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// Audio is delivered in blocks of this length, like a sound card would
+const BLOCK_MS: u32 = 10;
+
+pub struct WavSource {
+    samples: Vec<f32>,
+    channels: usize,
+    sample_rate: u32,
+    /// Pace delivery like a live device instead of delivering everything at once
+    realtime: bool,
+    stopped: Arc<AtomicBool>,
+    player: Option<JoinHandle<()>>,
+}
+
+impl WavSource {
+    pub fn open(path: &Path, realtime: bool) -> Result<Self, String> {
+        let audio = crate::audio::read_wav(path)?;
+        if audio.channels == 0 || audio.sample_rate == 0 {
+            return Err(format!("{} contains no audio", path.display()));
+        }
+        Ok(Self {
+            samples: audio.samples,
+            channels: audio.channels,
+            sample_rate: audio.sample_rate,
+            realtime,
+            stopped: Arc::new(AtomicBool::new(false)),
+            player: None,
+        })
+    }
+}
+
+impl AudioSource for WavSource {
+    fn format(&self) -> (usize, u32) {
+        (self.channels, self.sample_rate)
+    }
+
+    fn start(&mut self, mut on_data: DataCallback, _on_error: ErrorCallback) -> Result<(), String> {
+        let block = (self.sample_rate * BLOCK_MS / 1000).max(1) as usize * self.channels;
+        let samples = std::mem::take(&mut self.samples);
+
+        if !self.realtime {
+            for chunk in samples.chunks(block) {
+                on_data(chunk);
+            }
+            return Ok(());
+        }
+
+        let stopped = self.stopped.clone();
+        let player = std::thread::Builder::new()
+            .name("wav-source".to_string())
+            .spawn(move || {
+                for chunk in samples.chunks(block) {
+                    if stopped.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    on_data(chunk);
+                    std::thread::sleep(Duration::from_millis(BLOCK_MS as u64));
+                }
+            })
+            .map_err(|e| format!("Failed to start WAV playback: {}", e))?;
+        self.player = Some(player);
+        Ok(())
+    }
+
+    fn stop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        if let Some(player) = self.player.take() {
+            let _ = player.join();
+        }
+    }
+}
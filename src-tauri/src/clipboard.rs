@@ -0,0 +1,274 @@
+// Clipboard preservation
+// Pasting replaces whatever the user had copied, so the clipboard is
+// snapshotted first (text, HTML and images) and put back once the target
+// application has read the pasted text. On X11 the text is served from our
+// own selection owner, which sees the paste being read; elsewhere there is
+// no way to see a read, so the snapshot is put back after `PASTE_SETTLE`.
+
+#[cfg(target_os = "linux")]
+mod x11;
+
+use arboard::ImageData;
+use std::sync::mpsc::Receiver;
+use std::sync::Mutex;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// How long to wait for the target application to read the paste
+const CONSUME_TIMEOUT: Duration = Duration::from_millis(2000);
+/// Reads that follow the first one within this time belong to the same paste
+const CONSUME_QUIET: Duration = Duration::from_millis(100);
+/// Wait before restoring where reads can't be seen
+const PASTE_SETTLE: Duration = Duration::from_millis(300);
+
+/// Kept for the life of the app, since on Linux the contents we set are
+/// served by this process and go away with the last `arboard::Clipboard`
+static CLIPBOARD: Mutex<Option<arboard::Clipboard>> = Mutex::new(None);
+
+/// The restore or offer that is still waiting for a paste to be read
+static PENDING: Mutex<Option<JoinHandle<()>>> = Mutex::new(None);
+
+fn with_clipboard<T>(
+    f: impl FnOnce(&mut arboard::Clipboard) -> Result<T, arboard::Error>,
+) -> Result<T, String> {
+    let mut clipboard = CLIPBOARD
+        .lock()
+        .map_err(|_| "Clipboard lock poisoned".to_string())?;
+    if clipboard.is_none() {
+        *clipboard = Some(
+            arboard::Clipboard::new().map_err(|e| format!("Failed to open clipboard: {}", e))?,
+        );
+    }
+    f(clipboard.as_mut().unwrap()).map_err(|e| format!("Clipboard request failed: {}", e))
+}
+
+/// Read one format, treating a missing format as empty
+fn optional<T>(result: Result<T, arboard::Error>) -> Result<Option<T>, arboard::Error> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(arboard::Error::ContentNotAvailable) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// What was on the clipboard before a paste
+#[derive(Debug, Default)]
+pub struct Snapshot {
+    text: Option<String>,
+    html: Option<String>,
+    image: Option<ImageData<'static>>,
+}
+
+/// The formats of a snapshot that can be written back together
+#[derive(Debug)]
+enum Contents<'a> {
+    Image(&'a ImageData<'static>),
+    Html(&'a str, Option<&'a str>),
+    Text(&'a str),
+    Empty,
+}
+
+impl Snapshot {
+    pub fn take() -> Result<Self, String> {
+        with_clipboard(|clipboard| {
+            Ok(Self {
+                text: optional(clipboard.get().text())?,
+                html: optional(clipboard.get().html())?,
+                image: optional(clipboard.get().image())?,
+            })
+        })
+    }
+
+    /// Only one kind can be written back: an image wins over HTML, which
+    /// keeps the plain text as its alternative
+    fn contents(&self) -> Contents<'_> {
+        match (&self.image, &self.html, &self.text) {
+            (Some(image), _, _) => Contents::Image(image),
+            (None, Some(html), text) => Contents::Html(html, text.as_deref()),
+            (None, None, Some(text)) => Contents::Text(text),
+            (None, None, None) => Contents::Empty,
+        }
+    }
+
+    pub fn restore(&self) -> Result<(), String> {
+        with_clipboard(|clipboard| match self.contents() {
+            Contents::Image(image) => clipboard.set_image(image.clone()),
+            Contents::Html(html, text) => clipboard.set_html(html, text),
+            Contents::Text(text) => clipboard.set_text(text),
+            Contents::Empty => clipboard.clear(),
+        })
+    }
+}
+
+/// Text placed on the clipboard for one paste
+pub struct Offer {
+    text: String,
+    #[cfg(target_os = "linux")]
+    selection: Option<x11::Selection>,
+}
+
+impl Offer {
+    /// Put `text` on the clipboard, writing it with `write` and waiting
+    /// `settle` for the write to land where we can't serve it ourselves
+    pub fn new(
+        text: &str,
+        settle: Duration,
+        write: impl FnOnce(&str) -> Result<(), String>,
+    ) -> Result<Self, String> {
+        #[cfg(target_os = "linux")]
+        if crate::injection::Session::detect() == crate::injection::Session::X11 {
+            if let Ok(selection) = x11::Selection::own(text) {
+                return Ok(Self {
+                    text: text.to_string(),
+                    selection: Some(selection),
+                });
+            }
+        }
+
+        write(text)?;
+        thread::sleep(settle);
+        Ok(Self {
+            text: text.to_string(),
+            #[cfg(target_os = "linux")]
+            selection: None,
+        })
+    }
+
+    /// Call right before pressing the paste shortcut. Reads before this come
+    /// from clipboard managers copying the text, not from the paste.
+    pub fn arm(&self) {
+        #[cfg(target_os = "linux")]
+        if let Some(selection) = &self.selection {
+            selection.arm();
+        }
+    }
+
+    /// Block until the paste has been read, or as long as it may take
+    fn wait(&self) {
+        #[cfg(target_os = "linux")]
+        if let Some(selection) = &self.selection {
+            wait_for_reads(selection.reads(), CONSUME_TIMEOUT, CONSUME_QUIET);
+            return;
+        }
+        thread::sleep(PASTE_SETTLE);
+    }
+
+    /// Whether the clipboard still holds our text, and not something the
+    /// user copied since
+    fn still_ours(&self) -> bool {
+        #[cfg(target_os = "linux")]
+        if let Some(selection) = &self.selection {
+            return selection.owned();
+        }
+        with_clipboard(|clipboard| clipboard.get_text()).is_ok_and(|text| text == self.text)
+    }
+}
+
+/// Wait for the first read and then until reads stop coming. Returns
+/// whether anything was read before `timeout`.
+fn wait_for_reads(reads: &Receiver<()>, timeout: Duration, quiet: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    if reads
+        .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+        .is_err()
+    {
+        return false;
+    }
+    while Instant::now() < deadline && reads.recv_timeout(quiet).is_ok() {}
+    true
+}
+
+/// Wait for an earlier paste's restore, so a new snapshot doesn't pick up
+/// the text that was pasted
+pub fn wait_for_restore() {
+    let pending = PENDING.lock().ok().and_then(|mut pending| pending.take());
+    if let Some(pending) = pending {
+        let _ = pending.join();
+    }
+}
+
+/// Put `snapshot` back once `offer` has been pasted, unless something else
+/// was copied in the meantime. A failed restore is passed to `on_error`.
+pub fn restore_after(
+    offer: Offer,
+    snapshot: Snapshot,
+    on_error: impl FnOnce(String) + Send + 'static,
+) {
+    set_pending(thread::spawn(move || {
+        offer.wait();
+        if offer.still_ours() {
+            if let Err(e) = snapshot.restore() {
+                on_error(format!("Failed to restore clipboard: {}", e));
+            }
+        }
+    }));
+}
+
+/// Keep `offer` until it has been pasted when there is nothing to put back.
+/// Dropping it earlier gives up the X11 selection before the target reads it.
+pub fn release_after(offer: Offer) {
+    set_pending(thread::spawn(move || offer.wait()));
+}
+
+fn set_pending(handle: JoinHandle<()>) {
+    if let Ok(mut pending) = PENDING.lock() {
+        *pending = Some(handle);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::borrow::Cow;
+    use std::sync::mpsc;
+
+    #[test]
+    fn restores_the_richest_format() {
+        let image = ImageData {
+            width: 1,
+            height: 1,
+            bytes: Cow::Owned(vec![0, 0, 0, 255]),
+        };
+        let mut snapshot = Snapshot {
+            text: Some("plain".to_string()),
+            html: Some("<b>plain</b>".to_string()),
+            image: Some(image),
+        };
+        assert!(matches!(snapshot.contents(), Contents::Image(_)));
+
+        snapshot.image = None;
+        assert!(matches!(
+            snapshot.contents(),
+            Contents::Html("<b>plain</b>", Some("plain"))
+        ));
+        snapshot.html = None;
+        assert!(matches!(snapshot.contents(), Contents::Text("plain")));
+        assert!(matches!(Snapshot::default().contents(), Contents::Empty));
+    }
+
+    #[test]
+    fn waits_until_reads_stop() {
+        let (tx, rx) = mpsc::channel();
+        assert!(!wait_for_reads(
+            &rx,
+            Duration::from_millis(30),
+            Duration::from_millis(10)
+        ));
+
+        let reader = thread::spawn(move || {
+            for _ in 0..3 {
+                tx.send(()).unwrap();
+                thread::sleep(Duration::from_millis(5));
+            }
+            thread::sleep(Duration::from_secs(1));
+        });
+        let start = Instant::now();
+        assert!(wait_for_reads(
+            &rx,
+            Duration::from_secs(5),
+            Duration::from_millis(50)
+        ));
+        assert!(start.elapsed() < Duration::from_millis(500));
+        drop(reader);
+    }
+}
@@ -0,0 +1,218 @@
+// Serves pasted text as the owner of the CLIPBOARD selection, so we can see
+// when the target application reads it
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use x11rb::connection::{Connection, RequestConnection};
+use x11rb::protocol::xproto::{
+    Atom, AtomEnum, ConnectionExt, CreateWindowAux, EventMask, PropMode, SelectionNotifyEvent,
+    SelectionRequestEvent, Window, WindowClass, SELECTION_NOTIFY_EVENT,
+};
+use x11rb::protocol::Event;
+use x11rb::rust_connection::RustConnection;
+use x11rb::wrapper::ConnectionExt as _;
+use x11rb::{COPY_DEPTH_FROM_PARENT, COPY_FROM_PARENT, CURRENT_TIME, NONE};
+
+const POLL: Duration = Duration::from_millis(5);
+
+fn x11_error(e: impl std::fmt::Display) -> String {
+    format!("X11 request failed: {}", e)
+}
+
+struct Atoms {
+    clipboard: Atom,
+    targets: Atom,
+    /// Targets the text is served as
+    text: [Atom; 4],
+}
+
+/// Ownership of the clipboard, given up when dropped
+pub struct Selection {
+    armed: Arc<AtomicBool>,
+    owned: Arc<AtomicBool>,
+    stop: Arc<AtomicBool>,
+    reads: Receiver<()>,
+}
+
+impl Selection {
+    pub fn own(text: &str) -> Result<Self, String> {
+        let (conn, screen) =
+            x11rb::connect(None).map_err(|e| format!("Failed to connect to X11: {}", e))?;
+        // Anything bigger has to be sent in parts with INCR
+        if text.len() > conn.maximum_request_bytes() / 2 {
+            return Err("Text is too long to serve in one piece".to_string());
+        }
+        let atom = |name: &str| -> Result<Atom, String> {
+            Ok(conn
+                .intern_atom(false, name.as_bytes())
+                .map_err(x11_error)?
+                .reply()
+                .map_err(x11_error)?
+                .atom)
+        };
+        let atoms = Atoms {
+            clipboard: atom("CLIPBOARD")?,
+            targets: atom("TARGETS")?,
+            text: [
+                atom("UTF8_STRING")?,
+                atom("text/plain;charset=utf-8")?,
+                atom("TEXT")?,
+                AtomEnum::STRING.into(),
+            ],
+        };
+
+        let root = conn.setup().roots[screen].root;
+        let window = conn.generate_id().map_err(x11_error)?;
+        conn.create_window(
+            COPY_DEPTH_FROM_PARENT,
+            window,
+            root,
+            0,
+            0,
+            1,
+            1,
+            0,
+            WindowClass::INPUT_ONLY,
+            COPY_FROM_PARENT,
+            &CreateWindowAux::new(),
+        )
+        .map_err(x11_error)?;
+        conn.set_selection_owner(window, atoms.clipboard, CURRENT_TIME)
+            .map_err(x11_error)?;
+        let owner = conn
+            .get_selection_owner(atoms.clipboard)
+            .map_err(x11_error)?
+            .reply()
+            .map_err(x11_error)?
+            .owner;
+        if owner != window {
+            return Err("Could not take ownership of the clipboard".to_string());
+        }
+
+        let (tx, reads) = mpsc::channel();
+        let selection = Self {
+            armed: Arc::new(AtomicBool::new(false)),
+            owned: Arc::new(AtomicBool::new(true)),
+            stop: Arc::new(AtomicBool::new(false)),
+            reads,
+        };
+        let server = Server {
+            conn,
+            atoms,
+            text: text.as_bytes().to_vec(),
+            armed: selection.armed.clone(),
+            owned: selection.owned.clone(),
+            stop: selection.stop.clone(),
+            reads: tx,
+        };
+        thread::spawn(move || server.run(window));
+        Ok(selection)
+    }
+
+    pub fn arm(&self) {
+        self.armed.store(true, Ordering::SeqCst);
+    }
+
+    /// Signals each time the text is read after `arm`
+    pub fn reads(&self) -> &Receiver<()> {
+        &self.reads
+    }
+
+    /// Whether we still own the clipboard
+    pub fn owned(&self) -> bool {
+        self.owned.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for Selection {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+}
+
+struct Server {
+    conn: RustConnection,
+    atoms: Atoms,
+    text: Vec<u8>,
+    armed: Arc<AtomicBool>,
+    owned: Arc<AtomicBool>,
+    stop: Arc<AtomicBool>,
+    reads: Sender<()>,
+}
+
+impl Server {
+    /// Answer requests until someone else owns the clipboard or the
+    /// `Selection` is dropped, which closes the connection and the window
+    fn run(self, window: Window) {
+        while !self.stop.load(Ordering::SeqCst) {
+            match self.conn.poll_for_event() {
+                Ok(Some(Event::SelectionRequest(request))) if request.owner == window => {
+                    if self.answer(&request).unwrap_or(false) && self.armed.load(Ordering::SeqCst) {
+                        let _ = self.reads.send(());
+                    }
+                }
+                Ok(Some(Event::SelectionClear(_))) => break,
+                Ok(Some(_)) => {}
+                Ok(None) => thread::sleep(POLL),
+                Err(_) => break,
+            }
+        }
+        self.owned.store(false, Ordering::SeqCst);
+    }
+
+    /// Send the text or the list of targets. Returns whether the text was
+    /// sent.
+    fn answer(&self, request: &SelectionRequestEvent) -> Result<bool, String> {
+        // Old clients leave the property out and mean the target
+        let mut property = if request.property == NONE {
+            request.target
+        } else {
+            request.property
+        };
+        let mut sent_text = false;
+        if request.target == self.atoms.targets {
+            let mut targets = vec![self.atoms.targets];
+            targets.extend(self.atoms.text);
+            self.conn
+                .change_property32(
+                    PropMode::REPLACE,
+                    request.requestor,
+                    property,
+                    AtomEnum::ATOM,
+                    &targets,
+                )
+                .map_err(x11_error)?;
+        } else if self.atoms.text.contains(&request.target) {
+            self.conn
+                .change_property8(
+                    PropMode::REPLACE,
+                    request.requestor,
+                    property,
+                    request.target,
+                    &self.text,
+                )
+                .map_err(x11_error)?;
+            sent_text = true;
+        } else {
+            property = NONE;
+        }
+
+        let notify = SelectionNotifyEvent {
+            response_type: SELECTION_NOTIFY_EVENT,
+            sequence: 0,
+            time: request.time,
+            requestor: request.requestor,
+            selection: request.selection,
+            target: request.target,
+            property,
+        };
+        self.conn
+            .send_event(false, request.requestor, EventMask::NO_EVENT, notify)
+            .map_err(x11_error)?;
+        self.conn.flush().map_err(x11_error)?;
+        Ok(sent_text)
+    }
+}
@@ -1,64 +1,88 @@
-use tauri::{command, AppHandle, Runtime, WebviewWindow};
+use crate::focus;
+use crate::injection::{Chord, Method, Step};
+use crate::profiles::{self, InputMethod};
+use crate::settings;
+use crate::typing::{self, Outcome};
+use tauri::{command, AppHandle, Manager, Runtime, WebviewWindow};
 use tauri_plugin_clipboard_manager::ClipboardExt;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 #[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct TypeResult {
     pub success: bool,
     pub method: Option<String>,
     pub error: Option<String>,
     pub message: Option<String>,
+    /// Characters that reached the target application, also when typing
+    /// was cancelled or failed partway
+    pub delivered: Option<usize>,
+    /// Milliseconds from the command being called until the text was in
+    pub latency_ms: Option<u64>,
 }
 
-#[command]
-pub async fn type_text(text: String) -> TypeResult {
-    if text.trim().is_empty() {
-        return TypeResult {
+impl TypeResult {
+    fn failed(error: String) -> Self {
+        TypeResult {
             success: false,
             method: None,
-            error: Some("Empty text".to_string()),
+            error: Some(error),
             message: None,
+            delivered: None,
+            latency_ms: None,
+        }
+    }
+
+    /// Report the backend that delivered the text, or why none could, and
+    /// how long it took since `started`
+    fn from_typing(outcome: Outcome, started: Instant) -> Self {
+        let result = match outcome.result {
+            Ok(method) => TypeResult {
+                success: true,
+                method: Some(method.to_string()),
+                error: None,
+                message: None,
+                delivered: None,
+                latency_ms: None,
+            },
+            Err(e) => Self::failed(e),
         };
+        TypeResult {
+            delivered: Some(outcome.delivered),
+            latency_ms: Some(started.elapsed().as_millis() as u64),
+            ..result
+        }
     }
+}
 
-    match crate::keyboard::type_text(&text) {
-        Ok(_) => TypeResult {
-            success: true,
-            method: Some("enigo".to_string()),
-            error: None,
-            message: None,
-        },
-        Err(e) => TypeResult {
-            success: false,
-            method: None,
-            error: Some(e),
-            message: None,
-        },
+#[command]
+pub async fn type_text<R: Runtime>(app: AppHandle<R>, text: String) -> TypeResult {
+    let started = Instant::now();
+    if text.trim().is_empty() {
+        return TypeResult::failed("Empty text".to_string());
     }
+
+    let outcome = typing::run(app, vec![Step::Text(text)], Method::Type(None)).await;
+    TypeResult::from_typing(outcome, started)
 }
 
 #[command]
-pub async fn type_text_with_delay(text: String, delay_ms: Option<u64>) -> TypeResult {
-    let delay = delay_ms.unwrap_or(20);
+pub async fn type_text_with_delay<R: Runtime>(
+    app: AppHandle<R>,
+    text: String,
+    delay_ms: Option<u64>,
+) -> TypeResult {
+    let started = Instant::now();
+    let delay = Duration::from_millis(delay_ms.unwrap_or(20));
+    let method = Method::Type(Some(delay));
 
-    match crate::keyboard::type_text_with_delay(&text, delay) {
-        Ok(_) => TypeResult {
-            success: true,
-            method: Some("enigo-delayed".to_string()),
-            error: None,
-            message: None,
-        },
-        Err(e) => TypeResult {
-            success: false,
-            method: None,
-            error: Some(e),
-            message: None,
-        },
-    }
+    let outcome = typing::run(app, vec![Step::Text(text)], method).await;
+    TypeResult::from_typing(outcome, started)
 }
 
 /// Type text to the previously focused application
-/// Hides widget, waits for focus to return to previous app, then types
+/// Hides widget, re-activates the window dictation started in, then types
+/// the way that window's profile says
 #[command]
 pub async fn type_to_previous_app<R: Runtime>(
     window: WebviewWindow<R>,
@@ -67,83 +91,57 @@ pub async fn type_to_previous_app<R: Runtime>(
     hide_widget: bool,
     typing_delay: Option<u64>,
 ) -> TypeResult {
+    let started = Instant::now();
     if text.trim().is_empty() {
-        return TypeResult {
-            success: false,
-            method: None,
-            error: Some("Empty text".to_string()),
-            message: None,
-        };
+        return TypeResult::failed("Empty text".to_string());
     }
 
-    // Hide widget to return focus to previous app
-    if hide_widget {
-        let _ = window.hide();
-        // Wait for focus to transfer
-        std::thread::sleep(Duration::from_millis(150));
-    }
+    let target = focus::take();
+    // A broken profiles file would otherwise go unnoticed
+    let profile = match target.as_ref().map(profiles::find).transpose() {
+        Ok(profile) => profile.flatten(),
+        Err(e) => return TypeResult::failed(e),
+    };
+    let input_method = InputMethod::from_name(&input_method);
+    let (steps, method) = match profiles::plan(profile.as_ref(), &text, input_method, typing_delay)
+    {
+        Ok(plan) => plan,
+        Err(e) => return TypeResult::failed(e),
+    };
 
-    let result = if input_method == "paste" {
-        // Use clipboard paste method
-        match window.app_handle().clipboard().write_text(&text) {
-            Ok(_) => {
-                std::thread::sleep(Duration::from_millis(50));
-                match crate::keyboard::paste_shortcut() {
-                    Ok(_) => TypeResult {
-                        success: true,
-                        method: Some("clipboard-paste".to_string()),
-                        error: None,
-                        message: None,
-                    },
-                    Err(e) => TypeResult {
-                        success: false,
-                        method: None,
-                        error: Some(e),
-                        message: None,
-                    },
+    // Hide the widget and give focus back, which waits and polls, so it
+    // runs off the async runtime
+    let blocking_window = window.clone();
+    let focused = tauri::async_runtime::spawn_blocking(move || {
+        if hide_widget {
+            let _ = blocking_window.hide();
+        }
+        // Focus the window dictation started in, or without one, wait for
+        // focus to transfer
+        match &target {
+            Some(target) => focus::restore(target),
+            None => {
+                if hide_widget {
+                    let config = settings::load().unwrap_or_default().injection;
+                    std::thread::sleep(Duration::from_millis(config.hide_settle_ms));
                 }
+                Ok(())
             }
-            Err(e) => TypeResult {
-                success: false,
-                method: None,
-                error: Some(e.to_string()),
-                message: None,
-            },
         }
-    } else if input_method == "type_delayed" {
-        // Direct typing with delay
-        let delay = typing_delay.unwrap_or(20);
-        match crate::keyboard::type_text_with_delay(&text, delay) {
-            Ok(_) => TypeResult {
-                success: true,
-                method: Some("enigo-delayed".to_string()),
-                error: None,
-                message: None,
-            },
-            Err(e) => TypeResult {
-                success: false,
-                method: None,
-                error: Some(e),
-                message: None,
-            },
-        }
-    } else {
-        // Direct typing (fast)
-        match crate::keyboard::type_text(&text) {
-            Ok(_) => TypeResult {
-                success: true,
-                method: Some("enigo".to_string()),
-                error: None,
-                message: None,
-            },
-            Err(e) => TypeResult {
-                success: false,
-                method: None,
-                error: Some(e),
-                message: None,
-            },
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Failed to focus the target window: {}", e)));
+
+    let mut result = match focused {
+        Ok(()) => {
+            let outcome = typing::run(window.app_handle().clone(), steps, method).await;
+            TypeResult::from_typing(outcome, started)
         }
+        Err(e) => TypeResult::failed(e),
     };
+    if let Some(profile) = profile {
+        result.message = Some(format!("Used the {} profile", profile.name));
+    }
 
     // Show widget again after typing
     if hide_widget {
@@ -159,47 +157,11 @@ pub async fn type_to_previous_app<R: Runtime>(
 
 #[command]
 pub async fn paste_text<R: Runtime>(app: AppHandle<R>, text: String) -> TypeResult {
-    // Save current clipboard
-    let previous = app.clipboard().read_text().unwrap_or_default();
-
-    // Write new text to clipboard
-    if let Err(e) = app.clipboard().write_text(&text) {
-        return TypeResult {
-            success: false,
-            method: None,
-            error: Some(e.to_string()),
-            message: None,
-        };
-    }
-
-    // Small delay for clipboard update
-    std::thread::sleep(std::time::Duration::from_millis(50));
-
-    // Simulate paste
-    match crate::keyboard::paste_shortcut() {
-        Ok(_) => {
-            // Restore previous clipboard after delay
-            let app_clone = app.clone();
-            let prev = previous.unwrap_or_default();
-            std::thread::spawn(move || {
-                std::thread::sleep(std::time::Duration::from_millis(200));
-                let _ = app_clone.clipboard().write_text(&prev);
-            });
-
-            TypeResult {
-                success: true,
-                method: Some("clipboard-paste".to_string()),
-                error: None,
-                message: None,
-            }
-        }
-        Err(e) => TypeResult {
-            success: false,
-            method: None,
-            error: Some(e),
-            message: None,
-        },
-    }
+    let started = Instant::now();
+    // The clipboard backend puts back what was copied before
+    let method = Method::Paste(Chord::paste());
+    let outcome = typing::run(app, vec![Step::Text(text)], method).await;
+    TypeResult::from_typing(outcome, started)
 }
 
 #[command]
@@ -210,12 +172,16 @@ pub async fn copy_to_clipboard<R: Runtime>(app: AppHandle<R>, text: String) -> T
             method: None,
             error: None,
             message: None,
+            delivered: None,
+            latency_ms: None,
         },
         Err(e) => TypeResult {
             success: false,
             method: None,
             error: Some(e.to_string()),
             message: None,
+            delivered: None,
+            latency_ms: None,
         },
     }
 }
@@ -0,0 +1,245 @@
+// Focus tracking
+// The window that was active when dictation started is remembered so that
+// `type_to_previous_app` can activate that exact window again and confirm it
+// has focus before typing. It is forgotten once text has been sent to it. X11 is queried through `_NET_ACTIVE_WINDOW`; on
+// Wayland, Sway and Hyprland are asked over their IPC. Elsewhere nothing is
+// remembered and typing goes to whatever has focus.
+
+#[cfg(target_os = "linux")]
+mod wayland;
+#[cfg(target_os = "linux")]
+mod x11;
+
+use serde::Serialize;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::command;
+
+/// How long to wait for a re-activated window to get focus
+const FOCUS_TIMEOUT: Duration = Duration::from_millis(1000);
+const FOCUS_POLL: Duration = Duration::from_millis(20);
+
+/// Identifies a window to the window system that reported it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Handle {
+    X11(u32),
+    Sway(i64),
+    Hyprland(String),
+}
+
+/// A window text can be sent back to
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TargetWindow {
+    #[serde(skip)]
+    pub handle: Handle,
+    /// X11 `WM_CLASS` class or Wayland app id
+    pub class: Option<String>,
+    /// Name of the process that owns the window
+    pub process: Option<String>,
+    pub title: Option<String>,
+    #[serde(skip)]
+    pub pid: Option<u32>,
+}
+
+impl TargetWindow {
+    fn describe(&self) -> String {
+        self.title
+            .as_deref()
+            .or(self.class.as_deref())
+            .or(self.process.as_deref())
+            .map(|name| format!("'{}'", name))
+            .unwrap_or_else(|| "the target window".to_string())
+    }
+}
+
+/// Something that knows which window is active and can change it
+pub trait WindowSystem {
+    fn active_window(&self) -> Result<Option<TargetWindow>, String>;
+    fn exists(&self, handle: &Handle) -> Result<bool, String>;
+    fn is_active(&self, handle: &Handle) -> Result<bool, String>;
+    fn activate(&self, handle: &Handle) -> Result<(), String>;
+}
+
+/// Window dictation last started in
+static TARGET: Mutex<Option<TargetWindow>> = Mutex::new(None);
+
+#[cfg(target_os = "linux")]
+fn window_system() -> Option<Box<dyn WindowSystem>> {
+    use crate::injection::Session;
+    match Session::detect() {
+        Session::X11 => x11::X11::connect()
+            .ok()
+            .map(|x| Box::new(x) as Box<dyn WindowSystem>),
+        Session::Wayland => wayland::detect(),
+        Session::Other => None,
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn window_system() -> Option<Box<dyn WindowSystem>> {
+    None
+}
+
+/// Name of the process with `pid`
+pub fn process_name(pid: u32) -> Option<String> {
+    let comm = std::fs::read_to_string(format!("/proc/{}/comm", pid)).ok()?;
+    Some(comm.trim().to_string()).filter(|name| !name.is_empty())
+}
+
+/// Remember the active window as the target for typing. Our own windows are
+/// ignored, so the previous target stays when the widget has focus.
+pub fn remember() -> Option<TargetWindow> {
+    let active = window_system()?.active_window().ok()??;
+    if active.pid == Some(std::process::id()) {
+        return remembered();
+    }
+    if let Ok(mut target) = TARGET.lock() {
+        *target = Some(active.clone());
+    }
+    Some(active)
+}
+
+pub fn remembered() -> Option<TargetWindow> {
+    TARGET.lock().ok()?.clone()
+}
+
+/// Take the remembered window, so the next dictation doesn't reuse it
+pub fn take() -> Option<TargetWindow> {
+    TARGET.lock().ok()?.take()
+}
+
+/// Activate `target` and wait until it has focus
+pub fn restore(target: &TargetWindow) -> Result<(), String> {
+    let system = window_system().ok_or_else(|| {
+        format!(
+            "Could not reach the window system to focus {}",
+            target.describe()
+        )
+    })?;
+    restore_with(system.as_ref(), target, FOCUS_TIMEOUT)
+}
+
+fn restore_with(
+    system: &dyn WindowSystem,
+    target: &TargetWindow,
+    timeout: Duration,
+) -> Result<(), String> {
+    let closed = || {
+        format!(
+            "The window dictation started in ({}) was closed",
+            target.describe()
+        )
+    };
+    if !system.exists(&target.handle)? {
+        return Err(closed());
+    }
+    if system.is_active(&target.handle)? {
+        return Ok(());
+    }
+
+    system.activate(&target.handle)?;
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        thread::sleep(FOCUS_POLL);
+        if system.is_active(&target.handle)? {
+            return Ok(());
+        }
+    }
+    if !system.exists(&target.handle)? {
+        return Err(closed());
+    }
+    Err(format!("Could not focus {}", target.describe()))
+}
+
+/// Remember the active window as the target for `type_to_previous_app`
+#[command]
+pub fn remember_focused_window() -> Option<TargetWindow> {
+    remember()
+}
+
+#[command]
+pub fn get_target_window() -> Option<TargetWindow> {
+    remembered()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    /// One window that gets focus a few polls after it is activated
+    struct FakeSystem {
+        exists: bool,
+        polls_until_focus: Cell<u32>,
+        activated: Cell<bool>,
+    }
+
+    impl WindowSystem for FakeSystem {
+        fn active_window(&self) -> Result<Option<TargetWindow>, String> {
+            Ok(None)
+        }
+
+        fn exists(&self, _handle: &Handle) -> Result<bool, String> {
+            Ok(self.exists)
+        }
+
+        fn is_active(&self, _handle: &Handle) -> Result<bool, String> {
+            if !self.activated.get() {
+                return Ok(false);
+            }
+            let left = self.polls_until_focus.get();
+            self.polls_until_focus.set(left.saturating_sub(1));
+            Ok(left == 0)
+        }
+
+        fn activate(&self, _handle: &Handle) -> Result<(), String> {
+            self.activated.set(true);
+            Ok(())
+        }
+    }
+
+    fn editor() -> TargetWindow {
+        TargetWindow {
+            handle: Handle::X11(42),
+            class: Some("gedit".to_string()),
+            process: None,
+            title: Some("notes.txt".to_string()),
+            pid: None,
+        }
+    }
+
+    fn system(exists: bool, polls_until_focus: u32) -> FakeSystem {
+        FakeSystem {
+            exists,
+            polls_until_focus: Cell::new(polls_until_focus),
+            activated: Cell::new(false),
+        }
+    }
+
+    #[test]
+    fn waits_for_the_window_to_take_focus() {
+        let system = system(true, 3);
+        restore_with(&system, &editor(), Duration::from_secs(1)).unwrap();
+        assert!(system.activated.get());
+    }
+
+    #[test]
+    fn fails_when_the_window_is_gone_or_never_focused() {
+        let error = restore_with(&system(false, 0), &editor(), Duration::from_secs(1)).unwrap_err();
+        assert!(
+            error.contains("'notes.txt'") && error.contains("closed"),
+            "{}",
+            error
+        );
+
+        let error = restore_with(
+            &system(true, u32::MAX),
+            &editor(),
+            Duration::from_millis(60),
+        )
+        .unwrap_err();
+        assert!(error.starts_with("Could not focus"), "{}", error);
+    }
+}
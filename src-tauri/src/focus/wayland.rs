@@ -0,0 +1,173 @@
+// Wayland has no common protocol for reading or changing the active window,
+// so only compositors with an IPC for it are supported
+
+use super::{process_name, Handle, TargetWindow, WindowSystem};
+use serde_json::Value;
+use std::io::ErrorKind;
+use std::process::Command;
+
+fn output(program: &str, args: &[&str]) -> Result<Value, String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| match e.kind() {
+            ErrorKind::NotFound => format!("{} is not installed", program),
+            _ => format!("Failed to run {}: {}", program, e),
+        })?;
+    if !output.status.success() {
+        return Err(format!("{} failed ({})", program, output.status));
+    }
+    serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("Unexpected output from {}: {}", program, e))
+}
+
+fn string(value: &Value) -> Option<String> {
+    value.as_str().filter(|s| !s.is_empty()).map(str::to_string)
+}
+
+fn target(handle: Handle, class: Option<String>, title: &Value, pid: &Value) -> TargetWindow {
+    let pid = pid.as_u64().map(|pid| pid as u32);
+    TargetWindow {
+        handle,
+        class,
+        process: pid.and_then(process_name),
+        title: string(title),
+        pid,
+    }
+}
+
+/// The window system of the running compositor, if it is one we can talk to
+pub fn detect() -> Option<Box<dyn WindowSystem>> {
+    if std::env::var_os("SWAYSOCK").is_some() {
+        Some(Box::new(Sway))
+    } else if std::env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some() {
+        Some(Box::new(Hyprland))
+    } else {
+        None
+    }
+}
+
+pub struct Sway;
+
+impl Sway {
+    /// Every window in the layout tree
+    fn windows() -> Result<Vec<Value>, String> {
+        fn walk(node: &Value, windows: &mut Vec<Value>) {
+            if node.get("pid").is_some() {
+                windows.push(node.clone());
+            }
+            for key in ["nodes", "floating_nodes"] {
+                for child in node[key].as_array().into_iter().flatten() {
+                    walk(child, windows);
+                }
+            }
+        }
+        let mut windows = Vec::new();
+        walk(&output("swaymsg", &["-t", "get_tree"])?, &mut windows);
+        Ok(windows)
+    }
+}
+
+impl WindowSystem for Sway {
+    fn active_window(&self) -> Result<Option<TargetWindow>, String> {
+        let Some(window) = Self::windows()?.into_iter().find(|w| w["focused"] == true) else {
+            return Ok(None);
+        };
+        let Some(id) = window["id"].as_i64() else {
+            return Ok(None);
+        };
+        // XWayland windows have no app id but keep their X11 class
+        let class =
+            string(&window["app_id"]).or_else(|| string(&window["window_properties"]["class"]));
+        Ok(Some(target(
+            Handle::Sway(id),
+            class,
+            &window["name"],
+            &window["pid"],
+        )))
+    }
+
+    fn exists(&self, handle: &Handle) -> Result<bool, String> {
+        let Handle::Sway(id) = *handle else {
+            return Ok(false);
+        };
+        Ok(Self::windows()?.iter().any(|w| w["id"] == id))
+    }
+
+    fn is_active(&self, handle: &Handle) -> Result<bool, String> {
+        let Handle::Sway(id) = *handle else {
+            return Ok(false);
+        };
+        Ok(Self::windows()?
+            .iter()
+            .any(|w| w["id"] == id && w["focused"] == true))
+    }
+
+    fn activate(&self, handle: &Handle) -> Result<(), String> {
+        let Handle::Sway(id) = *handle else {
+            return Err("Not a Sway window".to_string());
+        };
+        output("swaymsg", &[&format!("[con_id={}]", id), "focus"]).map(|_| ())
+    }
+}
+
+pub struct Hyprland;
+
+impl Hyprland {
+    fn address(handle: &Handle) -> Option<&str> {
+        match handle {
+            Handle::Hyprland(address) => Some(address),
+            _ => None,
+        }
+    }
+}
+
+impl WindowSystem for Hyprland {
+    fn active_window(&self) -> Result<Option<TargetWindow>, String> {
+        let window = output("hyprctl", &["activewindow", "-j"])?;
+        let Some(address) = string(&window["address"]) else {
+            return Ok(None);
+        };
+        let class = string(&window["class"]);
+        Ok(Some(target(
+            Handle::Hyprland(address),
+            class,
+            &window["title"],
+            &window["pid"],
+        )))
+    }
+
+    fn exists(&self, handle: &Handle) -> Result<bool, String> {
+        let Some(address) = Self::address(handle) else {
+            return Ok(false);
+        };
+        let clients = output("hyprctl", &["clients", "-j"])?;
+        Ok(clients
+            .as_array()
+            .into_iter()
+            .flatten()
+            .any(|c| c["address"] == address))
+    }
+
+    fn is_active(&self, handle: &Handle) -> Result<bool, String> {
+        let Some(address) = Self::address(handle) else {
+            return Ok(false);
+        };
+        Ok(output("hyprctl", &["activewindow", "-j"])?["address"] == address)
+    }
+
+    fn activate(&self, handle: &Handle) -> Result<(), String> {
+        let Some(address) = Self::address(handle) else {
+            return Err("Not a Hyprland window".to_string());
+        };
+        let status = Command::new("hyprctl")
+            .args(["dispatch", "focuswindow", &format!("address:{}", address)])
+            .status()
+            .map_err(|e| format!("Failed to run hyprctl: {}", e))?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(format!("hyprctl failed ({})", status))
+        }
+    }
+}
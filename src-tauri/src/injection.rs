@@ -0,0 +1,527 @@
+// Text injection
+// Text reaches other applications through a `TextInjector`: enigo, the
+// clipboard and the paste shortcut, or the xdotool, wtype and ydotool
+// command-line tools. Backends are tried in the configured order until one
+// succeeds. Backends that can't work in the current session, such as the X11
+// ones on Wayland, are skipped and reported by `get_injection_diagnostics`.
+
+mod clipboard;
+mod enigo;
+mod keys;
+#[cfg(test)]
+pub mod mock;
+mod session;
+mod tools;
+
+pub use clipboard::ClipboardInjector;
+pub use enigo::EnigoInjector;
+pub use keys::{Chord, Key, Modifiers};
+pub use session::Session;
+pub use tools::{Tool, ToolInjector};
+
+use crate::settings;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+use tauri::{command, AppHandle, Runtime};
+
+const MAX_SETTLE_MS: u64 = 5000;
+
+/// Something that can send text to the focused window
+pub trait TextInjector: Send {
+    /// Reported as `TypeResult.method`
+    fn name(&self) -> &'static str;
+
+    /// Why this backend can't work here, checked before it is tried
+    fn unavailable(&self) -> Option<String> {
+        None
+    }
+
+    /// Type `text`, pausing `delay` after each character when given
+    fn type_text(&mut self, text: &str, delay: Option<Duration>) -> Result<(), Failure>;
+
+    /// Press a key chord, such as the paste shortcut
+    fn press(&mut self, chord: Chord) -> Result<(), Failure>;
+}
+
+/// Why a backend couldn't send something
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    pub error: String,
+    /// Part of it may have reached the target, so another backend must not
+    /// send it again
+    pub partial: bool,
+}
+
+impl Failure {
+    pub fn nothing_sent(error: impl Into<String>) -> Self {
+        Self {
+            error: error.into(),
+            partial: false,
+        }
+    }
+
+    pub fn partway(error: impl Into<String>) -> Self {
+        Self {
+            error: error.into(),
+            partial: true,
+        }
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.error)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Backend {
+    Enigo,
+    /// Clipboard plus the paste shortcut, sent by the other backends
+    Clipboard,
+    Xdotool,
+    Wtype,
+    Ydotool,
+}
+
+impl Backend {
+    fn tool(self) -> Option<Tool> {
+        match self {
+            Self::Xdotool => Some(Tool::Xdotool),
+            Self::Wtype => Some(Tool::Wtype),
+            Self::Ydotool => Some(Tool::Ydotool),
+            Self::Enigo | Self::Clipboard => None,
+        }
+    }
+}
+
+/// How text should reach the target application
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    /// Type it, pausing between characters when a delay is given
+    Type(Option<Duration>),
+    /// Paste it from the clipboard with the given shortcut, typing it if
+    /// pasting fails
+    Paste(Chord),
+}
+
+/// Part of an insertion
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    Text(String),
+    Press(Chord),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct InjectionConfig {
+    /// Backends to try, in order
+    pub backends: Vec<Backend>,
+    /// Wait before the first key press, for the target window to take focus
+    pub focus_settle_ms: u64,
+    /// Wait after hiding the widget when there is no remembered window to
+    /// focus
+    pub hide_settle_ms: u64,
+    /// Wait between writing the clipboard and pressing the paste shortcut
+    pub clipboard_settle_ms: u64,
+}
+
+impl Default for InjectionConfig {
+    fn default() -> Self {
+        let backends = if cfg!(target_os = "linux") {
+            vec![
+                Backend::Enigo,
+                Backend::Xdotool,
+                Backend::Wtype,
+                Backend::Ydotool,
+                Backend::Clipboard,
+            ]
+        } else {
+            vec![Backend::Enigo, Backend::Clipboard]
+        };
+        Self {
+            backends,
+            focus_settle_ms: 100,
+            hide_settle_ms: 150,
+            clipboard_settle_ms: 50,
+        }
+    }
+}
+
+impl InjectionConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.backends.is_empty() {
+            return Err("At least one text injection backend is needed".to_string());
+        }
+        for (i, backend) in self.backends.iter().enumerate() {
+            if self.backends[..i].contains(backend) {
+                return Err(format!("{:?} is listed more than once", backend));
+            }
+        }
+        let settles = [
+            self.focus_settle_ms,
+            self.hide_settle_ms,
+            self.clipboard_settle_ms,
+        ];
+        if settles.iter().any(|&ms| ms > MAX_SETTLE_MS) {
+            return Err(format!("Settle delays can be at most {} ms", MAX_SETTLE_MS));
+        }
+        Ok(())
+    }
+
+    /// Backends to try for `method`. Pasting tries the clipboard first.
+    pub fn order_for(&self, method: Method) -> Vec<Backend> {
+        let mut order = self.backends.clone();
+        if matches!(method, Method::Paste(_)) {
+            order.retain(|b| *b != Backend::Clipboard);
+            order.insert(0, Backend::Clipboard);
+        }
+        order
+    }
+}
+
+fn key_injector(backend: Backend) -> Option<Box<dyn TextInjector>> {
+    if backend == Backend::Enigo {
+        return Some(Box::new(EnigoInjector));
+    }
+    let tool = backend.tool()?;
+    Some(Box::new(ToolInjector::new(tool)))
+}
+
+/// Build the injectors for `order`. The clipboard backend presses `paste`
+/// with the others, in the same order, `clipboard_settle` after writing the
+/// clipboard.
+pub fn injectors<R: Runtime>(
+    app: &AppHandle<R>,
+    order: &[Backend],
+    paste: Chord,
+    clipboard_settle: Duration,
+) -> Vec<Box<dyn TextInjector>> {
+    order
+        .iter()
+        .map(|&backend| match key_injector(backend) {
+            Some(injector) => injector,
+            None => {
+                let keys = order.iter().filter_map(|&b| key_injector(b)).collect();
+                Box::new(ClipboardInjector::new(
+                    app.clone(),
+                    paste,
+                    clipboard_settle,
+                    keys,
+                ))
+            }
+        })
+        .collect()
+}
+
+/// Run `f` on each injector until one succeeds and return that one's index.
+/// A backend that fails after sending part of it stops the search, since
+/// the next one would send that part again.
+pub fn first_success(
+    injectors: &mut [Box<dyn TextInjector>],
+    mut f: impl FnMut(&mut dyn TextInjector) -> Result<(), Failure>,
+) -> Result<usize, Failure> {
+    let mut errors = Vec::new();
+    for (i, injector) in injectors.iter_mut().enumerate() {
+        if let Some(reason) = injector.unavailable() {
+            errors.push(format!("{}: {}", injector.name(), reason));
+            continue;
+        }
+        match f(injector.as_mut()) {
+            Ok(()) => return Ok(i),
+            Err(e) if e.partial => {
+                return Err(Failure::partway(format!(
+                    "{} stopped partway: {}",
+                    injector.name(),
+                    e
+                )))
+            }
+            Err(e) => errors.push(format!("{}: {}", injector.name(), e)),
+        }
+    }
+    if errors.is_empty() {
+        return Err(Failure::nothing_sent(
+            "No text injection backend configured",
+        ));
+    }
+    Err(Failure::nothing_sent(format!(
+        "Every text injection backend failed ({})",
+        errors.join("; ")
+    )))
+}
+
+fn perform(
+    injector: &mut dyn TextInjector,
+    step: &Step,
+    delay: Option<Duration>,
+) -> Result<(), Failure> {
+    match step {
+        Step::Text(text) => injector.type_text(text, delay),
+        Step::Press(chord) => injector.press(*chord),
+    }
+}
+
+/// Send `steps` with the first of `injectors` that works. The backend that
+/// sends the first step sends the rest too, so nothing is inserted twice.
+/// `sent` is called after each step and stops the rest when it fails.
+pub fn inject_with(
+    injectors: &mut [Box<dyn TextInjector>],
+    steps: &[Step],
+    delay: Option<Duration>,
+    mut sent: impl FnMut(&Step) -> Result<(), String>,
+) -> Result<&'static str, String> {
+    let Some((first, rest)) = steps.split_first() else {
+        return Err("Nothing to insert".to_string());
+    };
+    let i = first_success(injectors, |injector| perform(injector, first, delay))
+        .map_err(|e| e.error)?;
+    let injector = injectors[i].as_mut();
+    sent(first)?;
+    for step in rest {
+        perform(injector, step, delay)
+            .map_err(|e| format!("{} stopped partway: {}", injector.name(), e))?;
+        sent(step)?;
+    }
+    Ok(injector.name())
+}
+
+/// Send `steps` to the focused window with the configured backends and
+/// return the name of the one that worked
+pub fn inject<R: Runtime>(
+    app: &AppHandle<R>,
+    steps: &[Step],
+    method: Method,
+    mut sent: impl FnMut(&Step) -> Result<(), String>,
+) -> Result<&'static str, String> {
+    let config = settings::load()?.injection;
+    let (delay, paste) = match method {
+        Method::Type(delay) => (delay, Chord::paste()),
+        Method::Paste(chord) => (None, chord),
+    };
+    let clipboard_settle = Duration::from_millis(config.clipboard_settle_ms);
+    let mut injectors = injectors(app, &config.order_for(method), paste, clipboard_settle);
+    std::thread::sleep(Duration::from_millis(config.focus_settle_ms));
+    let mut stopped = false;
+    let result = inject_with(&mut injectors, steps, delay, |step| {
+        sent(step).inspect_err(|_| stopped = true)
+    });
+    // Installing something won't help when typing was stopped on purpose
+    result.map_err(|e| match Session::detect().hint() {
+        Some(hint) if !stopped => format!("{}. {}.", e, hint),
+        _ => e,
+    })
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackendStatus {
+    pub backend: Backend,
+    pub available: bool,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InjectionDiagnostics {
+    pub session: Session,
+    /// The configured backends, in order
+    pub backends: Vec<BackendStatus>,
+    /// What to install when no backend is available
+    pub hint: Option<String>,
+}
+
+/// Which text injection backends can work in this session, and why the
+/// others can't
+#[command]
+pub fn get_injection_diagnostics<R: Runtime>(
+    app: AppHandle<R>,
+) -> Result<InjectionDiagnostics, String> {
+    let config = settings::load()?.injection;
+    let order = config.backends;
+    let clipboard_settle = Duration::from_millis(config.clipboard_settle_ms);
+    let backends: Vec<BackendStatus> = order
+        .iter()
+        .zip(injectors(&app, &order, Chord::paste(), clipboard_settle))
+        .map(|(&backend, injector)| {
+            let reason = injector.unavailable();
+            BackendStatus {
+                backend,
+                available: reason.is_none(),
+                reason,
+            }
+        })
+        .collect();
+
+    let session = Session::detect();
+    let hint = if backends.iter().any(|b| b.available) {
+        None
+    } else {
+        session.hint().map(str::to_string)
+    };
+    Ok(InjectionDiagnostics {
+        session,
+        backends,
+        hint,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::{Call, RecordingInjector};
+    use super::*;
+
+    #[test]
+    fn falls_back_and_reports_the_backend_that_worked() {
+        let broken = RecordingInjector::failing("broken", "no display");
+        let working = RecordingInjector::new("working");
+        let unused = RecordingInjector::new("unused");
+        let (broken_calls, working_calls, unused_calls) = (
+            broken.calls.clone(),
+            working.calls.clone(),
+            unused.calls.clone(),
+        );
+
+        let mut injectors: Vec<Box<dyn TextInjector>> =
+            vec![Box::new(broken), Box::new(working), Box::new(unused)];
+        let delay = Some(Duration::from_millis(5));
+        let steps = [Step::Text("hello".to_string())];
+        let method = inject_with(&mut injectors, &steps, delay, |_| Ok(())).unwrap();
+
+        assert_eq!(method, "working");
+        let typed = Call::Type {
+            text: "hello".to_string(),
+            delay,
+        };
+        assert_eq!(*broken_calls.lock().unwrap(), vec![typed.clone()]);
+        assert_eq!(*working_calls.lock().unwrap(), vec![typed]);
+        assert!(unused_calls.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn reports_every_failure() {
+        let mut injectors: Vec<Box<dyn TextInjector>> = vec![
+            Box::new(RecordingInjector::failing("a", "not installed")),
+            Box::new(RecordingInjector::failing("b", "no display")),
+        ];
+        let error = first_success(&mut injectors, |i| i.press(Chord::paste())).unwrap_err();
+        assert!(error.error.contains("a: not installed"), "{}", error);
+        assert!(error.error.contains("b: no display"), "{}", error);
+    }
+
+    #[test]
+    fn does_not_retype_what_a_failed_backend_sent() {
+        let broken = RecordingInjector::failing_partway("broken", "display closed");
+        let other = RecordingInjector::new("other");
+        let other_calls = other.calls.clone();
+        let mut injectors: Vec<Box<dyn TextInjector>> = vec![Box::new(broken), Box::new(other)];
+
+        let steps = [Step::Text("hello".to_string())];
+        let error = inject_with(&mut injectors, &steps, None, |_| Ok(())).unwrap_err();
+        assert_eq!(error, "broken stopped partway: display closed");
+        assert!(other_calls.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn skips_unavailable_backends() {
+        let x11 = RecordingInjector::missing("x11", "only reaches XWayland windows on Wayland");
+        let x11_calls = x11.calls.clone();
+        let mut injectors: Vec<Box<dyn TextInjector>> =
+            vec![Box::new(x11), Box::new(RecordingInjector::new("wayland"))];
+
+        let steps = [Step::Text("hi".to_string())];
+        assert_eq!(
+            inject_with(&mut injectors, &steps, None, |_| Ok(())).unwrap(),
+            "wayland"
+        );
+        assert!(x11_calls.lock().unwrap().is_empty());
+
+        let mut injectors: Vec<Box<dyn TextInjector>> = vec![Box::new(RecordingInjector::missing(
+            "wtype",
+            "wtype is not installed",
+        ))];
+        let error = inject_with(&mut injectors, &steps, None, |_| Ok(())).unwrap_err();
+        assert!(error.contains("wtype: wtype is not installed"), "{}", error);
+    }
+
+    #[test]
+    fn detects_the_session_type() {
+        let detect = Session::from_vars;
+        assert_eq!(detect(Some("wayland"), None, Some(":0")), Session::Wayland);
+        assert_eq!(detect(Some("x11"), None, Some(":0")), Session::X11);
+        // XWayland sets DISPLAY as well
+        assert_eq!(
+            detect(None, Some("wayland-0"), Some(":0")),
+            Session::Wayland
+        );
+        assert_eq!(detect(Some("tty"), None, Some(":1")), Session::X11);
+        assert_eq!(detect(None, Some(""), None), Session::Other);
+    }
+
+    #[test]
+    fn sends_every_step_with_the_same_backend() {
+        let first = RecordingInjector::new("first");
+        let second = RecordingInjector::new("second");
+        let (first_calls, second_calls) = (first.calls.clone(), second.calls.clone());
+        let mut injectors: Vec<Box<dyn TextInjector>> = vec![Box::new(first), Box::new(second)];
+
+        let enter = Chord::key(Key::Enter);
+        let steps = [Step::Text("ls".to_string()), Step::Press(enter)];
+        assert_eq!(
+            inject_with(&mut injectors, &steps, None, |_| Ok(())).unwrap(),
+            "first"
+        );
+        assert_eq!(first_calls.lock().unwrap()[1], Call::Press(enter));
+        assert!(second_calls.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn stops_when_a_sent_step_is_refused() {
+        let typist = RecordingInjector::new("typist");
+        let calls = typist.calls.clone();
+        let mut injectors: Vec<Box<dyn TextInjector>> = vec![Box::new(typist)];
+
+        let steps = ["one", "two", "three"].map(|s| Step::Text(s.to_string()));
+        let mut sent = 0;
+        let error = inject_with(&mut injectors, &steps, None, |_| {
+            sent += 1;
+            if sent == 2 {
+                return Err("cancelled".to_string());
+            }
+            Ok(())
+        })
+        .unwrap_err();
+        assert_eq!(error, "cancelled");
+        assert_eq!(calls.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn pasting_tries_the_clipboard_first() {
+        let config = InjectionConfig {
+            backends: vec![Backend::Enigo, Backend::Wtype],
+            ..InjectionConfig::default()
+        };
+        assert_eq!(
+            config.order_for(Method::Paste(Chord::paste())),
+            vec![Backend::Clipboard, Backend::Enigo, Backend::Wtype]
+        );
+        assert_eq!(config.order_for(Method::Type(None)), config.backends);
+    }
+
+    #[test]
+    fn rejects_duplicate_backends() {
+        let config = InjectionConfig {
+            backends: vec![Backend::Enigo, Backend::Xdotool, Backend::Enigo],
+            ..InjectionConfig::default()
+        };
+        assert!(config.validate().is_err());
+        assert!(InjectionConfig::default().validate().is_ok());
+
+        let slow = InjectionConfig {
+            hide_settle_ms: 60_000,
+            ..InjectionConfig::default()
+        };
+        assert!(slow.validate().is_err());
+    }
+}
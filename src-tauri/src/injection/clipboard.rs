@@ -0,0 +1,85 @@
+use super::{Chord, Failure, TextInjector};
+use crate::clipboard::{self, Offer, Snapshot};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Runtime};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+/// Puts the text on the clipboard and presses `paste` with the first key
+/// backend that manages to. What was copied before is put back once the
+/// paste has been read; when that fails, `clipboard-error` is emitted.
+pub struct ClipboardInjector<R: Runtime> {
+    app: AppHandle<R>,
+    paste: Chord,
+    /// Wait between writing the clipboard and pasting
+    settle: Duration,
+    keys: Vec<Box<dyn TextInjector>>,
+}
+
+impl<R: Runtime> ClipboardInjector<R> {
+    pub fn new(
+        app: AppHandle<R>,
+        paste: Chord,
+        settle: Duration,
+        keys: Vec<Box<dyn TextInjector>>,
+    ) -> Self {
+        Self {
+            app,
+            paste,
+            settle,
+            keys,
+        }
+    }
+}
+
+impl<R: Runtime> TextInjector for ClipboardInjector<R> {
+    fn name(&self) -> &'static str {
+        "clipboard-paste"
+    }
+
+    fn unavailable(&self) -> Option<String> {
+        if self.keys.iter().all(|keys| keys.unavailable().is_some()) {
+            return Some("no backend can press the paste shortcut".to_string());
+        }
+        None
+    }
+
+    fn type_text(&mut self, text: &str, _delay: Option<Duration>) -> Result<(), Failure> {
+        clipboard::wait_for_restore();
+        let snapshot = Snapshot::take();
+        let offer = Offer::new(text, self.settle, |text| {
+            self.app
+                .clipboard()
+                .write_text(text)
+                .map_err(|e| format!("Failed to write clipboard: {}", e))
+        })
+        .map_err(Failure::nothing_sent)?;
+        offer.arm();
+        let pasted = self.press(self.paste);
+        match snapshot {
+            Ok(snapshot) if pasted.is_ok() => {
+                let app = self.app.clone();
+                clipboard::restore_after(offer, snapshot, move |e| report(&app, e));
+            }
+            Ok(snapshot) => {
+                drop(offer);
+                if let Err(e) = snapshot.restore() {
+                    report(&self.app, e);
+                }
+            }
+            Err(e) => {
+                clipboard::release_after(offer);
+                report(&self.app, format!("Clipboard not preserved: {}", e));
+            }
+        }
+        pasted
+    }
+
+    fn press(&mut self, chord: Chord) -> Result<(), Failure> {
+        super::first_success(&mut self.keys, |keys| keys.press(chord)).map(|_| ())
+    }
+}
+
+/// Tell the UI the user's clipboard contents were lost
+fn report<R: Runtime>(app: &AppHandle<R>, error: String) {
+    let _ = app.emit("clipboard-error", serde_json::json!({ "error": error }));
+}
@@ -0,0 +1,184 @@
+use super::keys::{self, Chord, Modifier};
+use super::{Failure, Session, TextInjector};
+use enigo::{Direction, Enigo, Key, Keyboard, Settings};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+/// Types through enigo: SendInput on Windows, CGEvent on macOS and XTest on
+/// Linux, which only reaches X11 and XWayland windows. The enigo connection
+/// lives on its own thread, so it is opened once rather than per insertion.
+pub struct EnigoInjector;
+
+enum Request {
+    Connect,
+    Text(String, Option<Duration>),
+    Press(Chord),
+}
+
+type Reply = Sender<Result<(), Failure>>;
+
+/// Channel to the thread that owns the enigo connection
+static SERVICE: Mutex<Option<Sender<(Request, Reply)>>> = Mutex::new(None);
+
+fn connect() -> Result<Enigo, String> {
+    Enigo::new(&Settings::default()).map_err(|e| format!("Failed to start enigo: {}", e))
+}
+
+fn serve(requests: Receiver<(Request, Reply)>) {
+    let mut connection: Option<Enigo> = None;
+    for (request, reply) in requests {
+        let enigo = match connection.take() {
+            Some(enigo) => Ok(enigo),
+            None => connect().map_err(Failure::nothing_sent),
+        };
+        let result = enigo.and_then(|mut enigo| {
+            let result = match request {
+                Request::Connect => Ok(()),
+                Request::Text(text, delay) => type_text(&mut enigo, &text, delay),
+                Request::Press(chord) => press(&mut enigo, chord),
+            };
+            // The display connection may be what broke, so a failed
+            // request reconnects next time
+            if result.is_ok() {
+                connection = Some(enigo);
+            }
+            result
+        });
+        let _ = reply.send(result);
+    }
+}
+
+/// Send `request` to the injector thread, starting it if needed, and wait
+/// for it to be carried out
+fn send(request: Request) -> Result<(), Failure> {
+    let stopped = "The keyboard injector thread stopped";
+    let (reply, answer) = mpsc::channel();
+    {
+        let mut service = SERVICE
+            .lock()
+            .map_err(|_| Failure::nothing_sent("Lock poisoned"))?;
+        if service.is_none() {
+            let (requests, receiver) = mpsc::channel();
+            thread::Builder::new()
+                .name("enigo-injector".to_string())
+                .spawn(move || serve(receiver))
+                .map_err(|e| {
+                    Failure::nothing_sent(format!("Failed to start the keyboard injector: {}", e))
+                })?;
+            *service = Some(requests);
+        }
+        let sent = service.as_ref().map(|s| s.send((request, reply)));
+        if !matches!(sent, Some(Ok(()))) {
+            // Started again on the next request
+            *service = None;
+            return Err(Failure::nothing_sent(stopped));
+        }
+    }
+    // Dying halfway through the request may have left some of it sent
+    answer.recv().map_err(|_| Failure::partway(stopped))?
+}
+
+impl EnigoInjector {
+    /// Open the enigo connection ahead of the first insertion
+    pub fn start() {
+        if Self.unavailable().is_none() {
+            thread::spawn(|| send(Request::Connect));
+        }
+    }
+}
+
+fn type_text(enigo: &mut Enigo, text: &str, delay: Option<Duration>) -> Result<(), Failure> {
+    // enigo may give up anywhere in the text
+    let Some(delay) = delay else {
+        return enigo
+            .text(text)
+            .map_err(|e| Failure::partway(e.to_string()));
+    };
+    for (i, c) in text.chars().enumerate() {
+        enigo.text(&c.to_string()).map_err(|e| match i {
+            0 => Failure::nothing_sent(e.to_string()),
+            _ => Failure::partway(e.to_string()),
+        })?;
+        thread::sleep(delay);
+    }
+    Ok(())
+}
+
+/// A chord either goes through as a whole or not at all
+fn press(enigo: &mut Enigo, chord: Chord) -> Result<(), Failure> {
+    press_keys(enigo, chord).map_err(Failure::nothing_sent)
+}
+
+fn press_keys(enigo: &mut Enigo, chord: Chord) -> Result<(), String> {
+    let modifiers: Vec<Key> = chord
+        .modifiers
+        .held()
+        .into_iter()
+        .map(|modifier| match modifier {
+            Modifier::Ctrl => Key::Control,
+            Modifier::Shift => Key::Shift,
+            Modifier::Alt => Key::Alt,
+            Modifier::Meta => Key::Meta,
+        })
+        .collect();
+
+    for &modifier in &modifiers {
+        enigo
+            .key(modifier, Direction::Press)
+            .map_err(|e| e.to_string())?;
+    }
+    let result = enigo
+        .key(enigo_key(chord.key), Direction::Click)
+        .map_err(|e| e.to_string());
+    // Release the modifiers even when the key failed, or they stay stuck
+    for &modifier in modifiers.iter().rev() {
+        let _ = enigo.key(modifier, Direction::Release);
+    }
+    result
+}
+
+impl TextInjector for EnigoInjector {
+    fn name(&self) -> &'static str {
+        "enigo"
+    }
+
+    fn unavailable(&self) -> Option<String> {
+        if !cfg!(target_os = "linux") {
+            return None;
+        }
+        match Session::detect() {
+            Session::X11 => None,
+            // XTest goes through XWayland and silently misses native windows
+            Session::Wayland => Some("only reaches XWayland windows on Wayland".to_string()),
+            Session::Other => Some("no X11 display".to_string()),
+        }
+    }
+
+    fn type_text(&mut self, text: &str, delay: Option<Duration>) -> Result<(), Failure> {
+        send(Request::Text(text.to_string(), delay))
+    }
+
+    fn press(&mut self, chord: Chord) -> Result<(), Failure> {
+        send(Request::Press(chord))
+    }
+}
+
+fn enigo_key(key: keys::Key) -> Key {
+    match key {
+        keys::Key::Char(c) => Key::Unicode(c),
+        keys::Key::Enter => Key::Return,
+        keys::Key::Tab => Key::Tab,
+        keys::Key::Space => Key::Space,
+        keys::Key::Escape => Key::Escape,
+        keys::Key::Backspace => Key::Backspace,
+        keys::Key::Delete => Key::Delete,
+        keys::Key::Up => Key::UpArrow,
+        keys::Key::Down => Key::DownArrow,
+        keys::Key::Left => Key::LeftArrow,
+        keys::Key::Right => Key::RightArrow,
+        keys::Key::Home => Key::Home,
+        keys::Key::End => Key::End,
+    }
+}
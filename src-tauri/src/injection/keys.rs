@@ -0,0 +1,175 @@
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+    /// Cmd on macOS, Super on Linux, Windows key on Windows
+    pub meta: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Modifier {
+    Ctrl,
+    Shift,
+    Alt,
+    Meta,
+}
+
+impl Modifiers {
+    /// The modifiers that are held, in the order they are pressed
+    pub fn held(self) -> Vec<Modifier> {
+        [
+            (self.ctrl, Modifier::Ctrl),
+            (self.shift, Modifier::Shift),
+            (self.alt, Modifier::Alt),
+            (self.meta, Modifier::Meta),
+        ]
+        .into_iter()
+        .filter_map(|(on, modifier)| on.then_some(modifier))
+        .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Enter,
+    Tab,
+    Space,
+    Escape,
+    Backspace,
+    Delete,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+}
+
+const NAMED_KEYS: [(&str, Key); 12] = [
+    ("Enter", Key::Enter),
+    ("Tab", Key::Tab),
+    ("Space", Key::Space),
+    ("Escape", Key::Escape),
+    ("Backspace", Key::Backspace),
+    ("Delete", Key::Delete),
+    ("Up", Key::Up),
+    ("Down", Key::Down),
+    ("Left", Key::Left),
+    ("Right", Key::Right),
+    ("Home", Key::Home),
+    ("End", Key::End),
+];
+
+/// A key pressed together with modifiers, written like `Ctrl+Shift+V`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Chord {
+    pub modifiers: Modifiers,
+    pub key: Key,
+}
+
+impl Chord {
+    pub fn key(key: Key) -> Self {
+        Self {
+            modifiers: Modifiers::default(),
+            key,
+        }
+    }
+
+    /// The platform's paste shortcut
+    pub fn paste() -> Self {
+        let modifiers = Modifiers {
+            ctrl: !cfg!(target_os = "macos"),
+            meta: cfg!(target_os = "macos"),
+            ..Modifiers::default()
+        };
+        Self {
+            modifiers,
+            key: Key::Char('v'),
+        }
+    }
+}
+
+impl FromStr for Chord {
+    type Err = String;
+
+    fn from_str(chord: &str) -> Result<Self, String> {
+        let invalid = |reason: &str| format!("Invalid key chord '{}': {}", chord, reason);
+        let mut parts: Vec<&str> = chord.split('+').map(str::trim).collect();
+        let key = parts
+            .pop()
+            .filter(|k| !k.is_empty())
+            .ok_or_else(|| invalid("no key"))?;
+
+        let mut modifiers = Modifiers::default();
+        for part in parts {
+            let flag = match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => &mut modifiers.ctrl,
+                "shift" => &mut modifiers.shift,
+                "alt" | "option" => &mut modifiers.alt,
+                "super" | "meta" | "cmd" | "command" => &mut modifiers.meta,
+                "commandorcontrol" | "cmdorctrl" => {
+                    if cfg!(target_os = "macos") {
+                        &mut modifiers.meta
+                    } else {
+                        &mut modifiers.ctrl
+                    }
+                }
+                _ => return Err(invalid(&format!("unknown modifier '{}'", part))),
+            };
+            *flag = true;
+        }
+
+        let key = if key.eq_ignore_ascii_case("Return") {
+            "Enter"
+        } else {
+            key
+        };
+        let mut chars = key.chars();
+        let key = match (chars.next(), chars.next()) {
+            (Some(c), None) => Key::Char(c.to_ascii_lowercase()),
+            _ => NAMED_KEYS
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(key))
+                .map(|&(_, key)| key)
+                .ok_or_else(|| invalid(&format!("unknown key '{}'", key)))?,
+        };
+        Ok(Self { modifiers, key })
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for modifier in self.modifiers.held() {
+            write!(f, "{:?}+", modifier)?;
+        }
+        match self.key {
+            Key::Char(c) => write!(f, "{}", c.to_ascii_uppercase()),
+            key => {
+                let name = NAMED_KEYS.iter().find(|(_, k)| *k == key).map(|(n, _)| *n);
+                write!(f, "{}", name.unwrap_or("?"))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_prints_chords() {
+        let chord: Chord = "ctrl + shift + v".parse().unwrap();
+        assert!(chord.modifiers.ctrl && chord.modifiers.shift && !chord.modifiers.alt);
+        assert_eq!(chord.key, Key::Char('v'));
+        assert_eq!(chord.to_string(), "Ctrl+Shift+V");
+
+        assert_eq!("Return".parse::<Chord>().unwrap(), Chord::key(Key::Enter));
+        assert!("Hyper+V".parse::<Chord>().is_err());
+        assert!("Ctrl+".parse::<Chord>().is_err());
+    }
+}
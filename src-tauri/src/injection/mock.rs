@@ -0,0 +1,84 @@
+use super::{Chord, Failure, TextInjector};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Call {
+    Type {
+        text: String,
+        delay: Option<Duration>,
+    },
+    Press(Chord),
+}
+
+/// Records what it is asked to do instead of touching the keyboard
+pub struct RecordingInjector {
+    name: &'static str,
+    error: Option<Failure>,
+    missing: Option<String>,
+    pub calls: Arc<Mutex<Vec<Call>>>,
+}
+
+impl RecordingInjector {
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            error: None,
+            missing: None,
+            calls: Arc::default(),
+        }
+    }
+
+    /// A backend that records every call and then fails it with `error`
+    pub fn failing(name: &'static str, error: &str) -> Self {
+        Self {
+            error: Some(Failure::nothing_sent(error)),
+            ..Self::new(name)
+        }
+    }
+
+    /// Like `failing`, but as if part of each call got through
+    pub fn failing_partway(name: &'static str, error: &str) -> Self {
+        Self {
+            error: Some(Failure::partway(error)),
+            ..Self::new(name)
+        }
+    }
+
+    /// A backend that reports itself unavailable for `reason`
+    pub fn missing(name: &'static str, reason: &str) -> Self {
+        Self {
+            missing: Some(reason.to_string()),
+            ..Self::new(name)
+        }
+    }
+
+    fn record(&mut self, call: Call) -> Result<(), Failure> {
+        self.calls.lock().unwrap().push(call);
+        match &self.error {
+            Some(error) => Err(error.clone()),
+            None => Ok(()),
+        }
+    }
+}
+
+impl TextInjector for RecordingInjector {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn unavailable(&self) -> Option<String> {
+        self.missing.clone()
+    }
+
+    fn type_text(&mut self, text: &str, delay: Option<Duration>) -> Result<(), Failure> {
+        self.record(Call::Type {
+            text: text.to_string(),
+            delay,
+        })
+    }
+
+    fn press(&mut self, chord: Chord) -> Result<(), Failure> {
+        self.record(Call::Press(chord))
+    }
+}
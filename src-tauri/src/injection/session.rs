@@ -0,0 +1,78 @@
+use serde::Serialize;
+use std::env;
+use std::path::PathBuf;
+
+/// The display server text has to reach
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Session {
+    X11,
+    Wayland,
+    /// Windows, macOS, or Linux without a display server
+    Other,
+}
+
+impl Session {
+    pub fn detect() -> Self {
+        if !cfg!(target_os = "linux") {
+            return Self::Other;
+        }
+        Self::from_vars(
+            env::var("XDG_SESSION_TYPE").ok().as_deref(),
+            env::var("WAYLAND_DISPLAY").ok().as_deref(),
+            env::var("DISPLAY").ok().as_deref(),
+        )
+    }
+
+    /// `XDG_SESSION_TYPE` wins; without it, a Wayland socket means Wayland
+    /// even when XWayland also sets `DISPLAY`
+    pub fn from_vars(
+        session_type: Option<&str>,
+        wayland_display: Option<&str>,
+        display: Option<&str>,
+    ) -> Self {
+        let set = |var: Option<&str>| var.is_some_and(|v| !v.is_empty());
+        match session_type {
+            Some("wayland") => Self::Wayland,
+            Some("x11") => Self::X11,
+            _ if set(wayland_display) => Self::Wayland,
+            _ if set(display) => Self::X11,
+            _ => Self::Other,
+        }
+    }
+
+    /// What to install when nothing can type in this session
+    pub fn hint(self) -> Option<&'static str> {
+        match self {
+            Self::Wayland => Some(
+                "On Wayland, install wtype (Sway, Hyprland, KDE and other compositors with the \
+                 virtual keyboard protocol) or ydotool and start ydotoold (any compositor, \
+                 including GNOME)",
+            ),
+            Self::X11 => Some("On X11, install xdotool or libxdo"),
+            Self::Other if cfg!(target_os = "linux") => {
+                Some("No X11 or Wayland display was found; text can only be typed in a graphical session")
+            }
+            Self::Other => None,
+        }
+    }
+}
+
+/// Whether `program` is somewhere on `PATH`
+pub fn installed(program: &str) -> bool {
+    env::var_os("PATH")
+        .is_some_and(|paths| env::split_paths(&paths).any(|dir| dir.join(program).is_file()))
+}
+
+/// Whether ydotoold's socket exists
+pub fn ydotoold_running() -> bool {
+    let mut sockets: Vec<PathBuf> = env::var_os("YDOTOOL_SOCKET")
+        .map(PathBuf::from)
+        .into_iter()
+        .collect();
+    if let Some(runtime) = env::var_os("XDG_RUNTIME_DIR") {
+        sockets.push(PathBuf::from(runtime).join(".ydotool_socket"));
+    }
+    sockets.push(PathBuf::from("/tmp/.ydotool_socket"));
+    sockets.iter().any(|path| path.exists())
+}
@@ -0,0 +1,218 @@
+use super::keys::{Chord, Key, Modifier};
+use super::session::{self, Session};
+use super::{Failure, TextInjector};
+use std::io::ErrorKind;
+use std::process::Command;
+use std::time::Duration;
+
+/// Linux command-line tools that can synthesize key presses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tool {
+    /// X11 only
+    Xdotool,
+    /// Wayland compositors with the virtual keyboard protocol
+    Wtype,
+    /// Any session, through uinput; needs the ydotoold daemon
+    Ydotool,
+}
+
+impl Tool {
+    pub fn program(self) -> &'static str {
+        match self {
+            Self::Xdotool => "xdotool",
+            Self::Wtype => "wtype",
+            Self::Ydotool => "ydotool",
+        }
+    }
+}
+
+/// Types by running one of the `Tool`s
+pub struct ToolInjector {
+    tool: Tool,
+}
+
+impl ToolInjector {
+    pub fn new(tool: Tool) -> Self {
+        Self { tool }
+    }
+
+    /// The tools connect to the display or daemon before sending any key,
+    /// and that is where they fail in practice, so a failure is taken to
+    /// mean nothing was sent
+    fn run(&self, args: &[&str]) -> Result<(), Failure> {
+        let program = self.tool.program();
+        let output = Command::new(program)
+            .args(args)
+            .output()
+            .map_err(|e| match e.kind() {
+                ErrorKind::NotFound => format!("{} is not installed", program),
+                _ => format!("Failed to run {}: {}", program, e),
+            })
+            .map_err(Failure::nothing_sent)?;
+        if output.status.success() {
+            return Ok(());
+        }
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let error = match stderr.trim() {
+            "" => format!("{} failed ({})", program, output.status),
+            message => format!("{} failed: {}", program, message),
+        };
+        Err(Failure::nothing_sent(error))
+    }
+}
+
+impl TextInjector for ToolInjector {
+    fn name(&self) -> &'static str {
+        self.tool.program()
+    }
+
+    fn unavailable(&self) -> Option<String> {
+        let program = self.tool.program();
+        if !cfg!(target_os = "linux") {
+            return Some(format!("{} only works on Linux", program));
+        }
+        if !session::installed(program) {
+            return Some(format!("{} is not installed", program));
+        }
+        match (self.tool, Session::detect()) {
+            (Tool::Xdotool, Session::Wayland) => {
+                Some("xdotool only reaches XWayland windows on Wayland".to_string())
+            }
+            (Tool::Xdotool, Session::Other) => Some("no X11 display".to_string()),
+            (Tool::Wtype, session) if session != Session::Wayland => {
+                Some("wtype needs a Wayland session".to_string())
+            }
+            (Tool::Ydotool, _) if !session::ydotoold_running() => {
+                Some("ydotoold is not running".to_string())
+            }
+            _ => None,
+        }
+    }
+
+    fn type_text(&mut self, text: &str, delay: Option<Duration>) -> Result<(), Failure> {
+        let delay_ms = delay.map(|d| d.as_millis().to_string());
+        let mut args = match (self.tool, delay_ms.as_deref()) {
+            (Tool::Xdotool, Some(ms)) => vec!["type", "--clearmodifiers", "--delay", ms],
+            (Tool::Xdotool, None) => vec!["type", "--clearmodifiers"],
+            (Tool::Wtype, Some(ms)) => vec!["-d", ms],
+            (Tool::Wtype, None) => vec![],
+            (Tool::Ydotool, Some(ms)) => vec!["type", "--key-delay", ms],
+            (Tool::Ydotool, None) => vec!["type"],
+        };
+        // Keep text starting with a dash from being read as an option
+        args.extend(["--", text]);
+        self.run(&args)
+    }
+
+    fn press(&mut self, chord: Chord) -> Result<(), Failure> {
+        let held = chord.modifiers.held();
+        match self.tool {
+            Tool::Xdotool => {
+                let mut names: Vec<&str> = held.iter().map(|&m| x11_modifier(m)).collect();
+                let key = keysym(chord.key);
+                names.push(&key);
+                self.run(&["key", "--clearmodifiers", &names.join("+")])
+            }
+            Tool::Wtype => {
+                let key = keysym(chord.key);
+                let mut args = Vec::new();
+                for &modifier in &held {
+                    args.extend(["-M", wtype_modifier(modifier)]);
+                }
+                args.extend(["-k", &key]);
+                for &modifier in held.iter().rev() {
+                    args.extend(["-m", wtype_modifier(modifier)]);
+                }
+                self.run(&args)
+            }
+            Tool::Ydotool => {
+                let key = input_code(chord.key).ok_or_else(|| {
+                    Failure::nothing_sent(format!("ydotool can't press {}", chord))
+                })?;
+                let codes: Vec<u16> = held.iter().map(|&m| modifier_code(m)).collect();
+                let mut presses: Vec<String> = codes.iter().map(|c| format!("{}:1", c)).collect();
+                presses.push(format!("{}:1", key));
+                presses.push(format!("{}:0", key));
+                presses.extend(codes.iter().rev().map(|c| format!("{}:0", c)));
+
+                let mut args = vec!["key"];
+                args.extend(presses.iter().map(String::as_str));
+                self.run(&args)
+            }
+        }
+    }
+}
+
+fn x11_modifier(modifier: Modifier) -> &'static str {
+    match modifier {
+        Modifier::Ctrl => "ctrl",
+        Modifier::Shift => "shift",
+        Modifier::Alt => "alt",
+        Modifier::Meta => "super",
+    }
+}
+
+fn wtype_modifier(modifier: Modifier) -> &'static str {
+    match modifier {
+        Modifier::Meta => "logo",
+        other => x11_modifier(other),
+    }
+}
+
+/// XKB keysym name, understood by both xdotool and wtype
+fn keysym(key: Key) -> String {
+    let name = match key {
+        Key::Char(c) => return c.to_string(),
+        Key::Enter => "Return",
+        Key::Tab => "Tab",
+        Key::Space => "space",
+        Key::Escape => "Escape",
+        Key::Backspace => "BackSpace",
+        Key::Delete => "Delete",
+        Key::Up => "Up",
+        Key::Down => "Down",
+        Key::Left => "Left",
+        Key::Right => "Right",
+        Key::Home => "Home",
+        Key::End => "End",
+    };
+    name.to_string()
+}
+
+/// Linux input event codes, which ydotool sends through uinput
+fn modifier_code(modifier: Modifier) -> u16 {
+    match modifier {
+        Modifier::Ctrl => 29,
+        Modifier::Shift => 42,
+        Modifier::Alt => 56,
+        Modifier::Meta => 125,
+    }
+}
+
+fn input_code(key: Key) -> Option<u16> {
+    // Scan codes follow the QWERTY rows, not the alphabet
+    const LETTERS: [u16; 26] = [
+        30, 48, 46, 32, 18, 33, 34, 35, 23, 36, 37, 38, 50, 49, 24, 25, 16, 19, 31, 20, 22, 47, 17,
+        45, 21, 44,
+    ];
+    let code = match key {
+        Key::Char(c @ 'a'..='z') => LETTERS[(c as u8 - b'a') as usize],
+        Key::Char('0') => 11,
+        Key::Char(c @ '1'..='9') => 2 + (c as u8 - b'1') as u16,
+        Key::Char(_) => return None,
+        Key::Enter => 28,
+        Key::Tab => 15,
+        Key::Space => 57,
+        Key::Escape => 1,
+        Key::Backspace => 14,
+        Key::Delete => 111,
+        Key::Up => 103,
+        Key::Down => 108,
+        Key::Left => 105,
+        Key::Right => 106,
+        Key::Home => 102,
+        Key::End => 107,
+    };
+    Some(code)
+}
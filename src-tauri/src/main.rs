@@ -1,12 +1,21 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-mod keyboard;
+mod audio;
+mod capture;
+mod clipboard;
 mod commands;
+mod focus;
+mod injection;
+mod profiles;
+mod settings;
+mod shortcuts;
+mod vocabulary;
 mod tray;
+mod typing;
+mod vad;
 mod whisper;
 
 use tauri::Manager;
-use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
 
 fn main() {
     tauri::Builder::default()
@@ -18,7 +27,10 @@ fn main() {
             tray::create_tray(app)?;
 
             // Register global shortcuts
-            register_shortcuts(app)?;
+            shortcuts::register_all(app.handle())?;
+
+            // Connect the keyboard injector before the first insertion
+            injection::EnigoInjector::start();
 
             // Position window in bottom-right corner
             if let Some(window) = app.get_webview_window("main") {
@@ -50,43 +62,61 @@ fn main() {
             commands::set_always_on_top,
             commands::get_always_on_top,
             commands::check_accessibility_permission,
+            settings::get_settings,
+            settings::set_shared_models_dir,
+            settings::get_transcribe_options,
+            settings::set_transcribe_options,
+            settings::get_vad_config,
+            settings::set_vad_config,
+            settings::get_injection_config,
+            settings::set_injection_config,
+            settings::get_typing_config,
+            settings::set_typing_config,
+            typing::cancel_typing,
+            injection::get_injection_diagnostics,
+            focus::remember_focused_window,
+            focus::get_target_window,
+            profiles::list_profiles,
+            profiles::save_profile,
+            profiles::delete_profile,
+            profiles::get_active_profile,
+            shortcuts::list_shortcuts,
+            shortcuts::register_shortcut,
+            shortcuts::unregister_shortcut,
+            shortcuts::rebind_shortcut,
+            shortcuts::reset_shortcuts,
+            shortcuts::set_dictation_mode,
+            vocabulary::list_vocabulary,
+            vocabulary::add_vocabulary_word,
+            vocabulary::remove_vocabulary_word,
             // Native Whisper commands
             whisper::is_whisper_model_downloaded,
             whisper::get_whisper_model_path,
             whisper::download_whisper_model,
+            whisper::import_whisper_model,
             whisper::load_whisper_model,
+            whisper::verify_whisper_model,
             whisper::is_whisper_loaded,
             whisper::transcribe_audio_native,
+            whisper::transcribe_audio_detailed,
             whisper::transcribe_audio_file,
+            whisper::start_stream,
+            whisper::push_audio,
+            whisper::finish_stream,
+            whisper::cancel_stream,
+            whisper::start_file_transcription,
+            whisper::cancel_transcription,
+            whisper::get_transcription_job,
+            whisper::list_transcription_jobs,
+            capture::list_audio_devices,
+            capture::start_recording,
+            capture::stop_recording,
             whisper::unload_whisper_model,
+            whisper::list_whisper_models,
+            whisper::delete_whisper_model,
+            whisper::list_whisper_catalog,
+            whisper::set_default_whisper_model,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
-
-fn register_shortcuts<R: tauri::Runtime>(app: &tauri::App<R>) -> Result<(), Box<dyn std::error::Error>> {
-    // Toggle dictation: Ctrl+Shift+D / Cmd+Shift+D
-    let toggle_shortcut: Shortcut = "CommandOrControl+Shift+D".parse()?;
-    app.global_shortcut().on_shortcut(toggle_shortcut, |app, _shortcut, _event| {
-        if let Some(window) = app.get_webview_window("main") {
-            let _ = window.show();
-            let _ = window.set_focus();
-            let _ = window.emit("toggle-dictation", ());
-        }
-    })?;
-
-    // Show/hide widget: Ctrl+Shift+H / Cmd+Shift+H
-    let visibility_shortcut: Shortcut = "CommandOrControl+Shift+H".parse()?;
-    app.global_shortcut().on_shortcut(visibility_shortcut, |app, _shortcut, _event| {
-        if let Some(window) = app.get_webview_window("main") {
-            if window.is_visible().unwrap_or(false) {
-                let _ = window.hide();
-            } else {
-                let _ = window.show();
-                let _ = window.set_focus();
-            }
-        }
-    })?;
-
-    Ok(())
-}
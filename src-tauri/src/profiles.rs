@@ -0,0 +1,306 @@
+// Per-application input profiles
+// How text goes into a particular application: terminals that paste with
+// Ctrl+Shift+V, chat apps where Enter sends the message, Electron apps that
+// drop fast input. Profiles are stored in profiles.json and matched against
+// the window dictation started in.
+
+use crate::focus::{self, TargetWindow};
+use crate::injection::{Chord, Key, Method, Modifiers, Step};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::command;
+
+const PROFILES_FILE: &str = "profiles.json";
+const DEFAULT_TYPING_DELAY_MS: u64 = 20;
+
+/// The widget's input methods, named as `type_to_previous_app` takes them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InputMethod {
+    Type,
+    TypeDelayed,
+    Paste,
+}
+
+impl InputMethod {
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "paste" => Self::Paste,
+            "type_delayed" => Self::TypeDelayed,
+            _ => Self::Type,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Newlines {
+    /// Line breaks are typed or pasted as they are
+    #[default]
+    Keep,
+    /// Lines are joined with a space
+    Space,
+    /// Shift+Enter between lines, for chat apps where Enter sends
+    ShiftEnter,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Profile {
+    pub name: String,
+    /// X11 `WM_CLASS` classes or Wayland app ids, ignoring case
+    pub window_classes: Vec<String>,
+    /// Process names, ignoring case
+    pub processes: Vec<String>,
+    /// Overrides the input method chosen in the widget
+    pub input_method: Option<InputMethod>,
+    /// Shortcut that pastes, like `Ctrl+Shift+V`
+    pub paste_chord: Option<String>,
+    /// Delay between characters for `type_delayed`
+    pub typing_delay_ms: Option<u64>,
+    pub newlines: Newlines,
+    /// Keys pressed after the text, like `Enter` to send a message
+    pub after_insert: Vec<String>,
+}
+
+impl Profile {
+    fn matches(&self, window: &TargetWindow) -> bool {
+        let any = |names: &[String], value: &Option<String>| {
+            value
+                .as_deref()
+                .is_some_and(|value| names.iter().any(|n| n.eq_ignore_ascii_case(value)))
+        };
+        any(&self.window_classes, &window.class) || any(&self.processes, &window.process)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Profile name is empty".to_string());
+        }
+        if self.window_classes.is_empty() && self.processes.is_empty() {
+            return Err(format!(
+                "Profile {} needs a window class or process name",
+                self.name
+            ));
+        }
+        for chord in self.paste_chord.iter().chain(&self.after_insert) {
+            chord.parse::<Chord>()?;
+        }
+        Ok(())
+    }
+}
+
+fn default_profiles() -> Vec<Profile> {
+    let names = |names: &[&str]| names.iter().map(|n| n.to_string()).collect();
+    vec![Profile {
+        name: "Terminals".to_string(),
+        window_classes: names(&[
+            "gnome-terminal",
+            "org.gnome.Terminal",
+            "org.gnome.Ptyxis",
+            "konsole",
+            "org.kde.konsole",
+            "kitty",
+            "Alacritty",
+            "org.wezfurlong.wezterm",
+            "foot",
+            "com.mitchellh.ghostty",
+            "tilix",
+            "com.gexperts.Tilix",
+            "xfce4-terminal",
+            "terminator",
+        ]),
+        processes: names(&[
+            "gnome-terminal-server",
+            "ptyxis",
+            "konsole",
+            "kitty",
+            "alacritty",
+            "wezterm-gui",
+            "foot",
+            "ghostty",
+            "tilix",
+            "xfce4-terminal",
+            "terminator",
+        ]),
+        paste_chord: Some("Ctrl+Shift+V".to_string()),
+        ..Profile::default()
+    }]
+}
+
+fn profiles_path() -> Result<PathBuf, String> {
+    Ok(crate::settings::app_data_dir()?.join(PROFILES_FILE))
+}
+
+/// Load the stored profiles, using the defaults when there are none yet
+pub fn load() -> Result<Vec<Profile>, String> {
+    let path = profiles_path()?;
+    if !path.exists() {
+        return Ok(default_profiles());
+    }
+
+    let contents =
+        std::fs::read_to_string(&path).map_err(|e| format!("Failed to read profiles: {}", e))?;
+    serde_json::from_str(&contents)
+        .map_err(|e| format!("Invalid profiles file {}: {}", path.display(), e))
+}
+
+fn save(profiles: &[Profile]) -> Result<(), String> {
+    let contents = serde_json::to_string_pretty(profiles)
+        .map_err(|e| format!("Failed to serialize profiles: {}", e))?;
+    std::fs::write(profiles_path()?, contents)
+        .map_err(|e| format!("Failed to write profiles: {}", e))
+}
+
+/// The first profile that matches `window`
+pub fn find(window: &TargetWindow) -> Result<Option<Profile>, String> {
+    Ok(load()?.into_iter().find(|p| p.matches(window)))
+}
+
+/// What to send for `text` and how, with the profile's settings taking
+/// precedence over the widget's
+pub fn plan(
+    profile: Option<&Profile>,
+    text: &str,
+    input_method: InputMethod,
+    typing_delay_ms: Option<u64>,
+) -> Result<(Vec<Step>, Method), String> {
+    let input_method = profile.and_then(|p| p.input_method).unwrap_or(input_method);
+    let method = match input_method {
+        InputMethod::Type => Method::Type(None),
+        InputMethod::TypeDelayed => {
+            let delay = profile
+                .and_then(|p| p.typing_delay_ms)
+                .or(typing_delay_ms)
+                .unwrap_or(DEFAULT_TYPING_DELAY_MS);
+            Method::Type(Some(Duration::from_millis(delay)))
+        }
+        InputMethod::Paste => match profile.and_then(|p| p.paste_chord.as_deref()) {
+            Some(chord) => Method::Paste(chord.parse()?),
+            None => Method::Paste(Chord::paste()),
+        },
+    };
+
+    let mut steps = Vec::new();
+    match profile.map(|p| p.newlines).unwrap_or_default() {
+        Newlines::Keep => steps.push(Step::Text(text.to_string())),
+        Newlines::Space => steps.push(Step::Text(text.lines().collect::<Vec<_>>().join(" "))),
+        Newlines::ShiftEnter => {
+            let shift_enter = Chord {
+                modifiers: Modifiers {
+                    shift: true,
+                    ..Modifiers::default()
+                },
+                key: Key::Enter,
+            };
+            for (i, line) in text.lines().enumerate() {
+                if i > 0 {
+                    steps.push(Step::Press(shift_enter));
+                }
+                if !line.is_empty() {
+                    steps.push(Step::Text(line.to_string()));
+                }
+            }
+        }
+    }
+    for chord in profile.iter().flat_map(|p| &p.after_insert) {
+        steps.push(Step::Press(chord.parse()?));
+    }
+    Ok((steps, method))
+}
+
+#[command]
+pub fn list_profiles() -> Result<Vec<Profile>, String> {
+    load()
+}
+
+/// Add a profile, or replace the one with the same name
+#[command]
+pub fn save_profile(mut profile: Profile) -> Result<Vec<Profile>, String> {
+    profile.name = profile.name.trim().to_string();
+    profile.validate()?;
+    let mut profiles = load()?;
+    match profiles
+        .iter_mut()
+        .find(|p| p.name.eq_ignore_ascii_case(&profile.name))
+    {
+        Some(existing) => *existing = profile,
+        None => profiles.push(profile),
+    }
+    save(&profiles)?;
+    Ok(profiles)
+}
+
+#[command]
+pub fn delete_profile(name: String) -> Result<Vec<Profile>, String> {
+    let mut profiles = load()?;
+    profiles.retain(|p| !p.name.eq_ignore_ascii_case(name.trim()));
+    save(&profiles)?;
+    Ok(profiles)
+}
+
+/// The profile for the window text will be typed into
+#[command]
+pub fn get_active_profile() -> Result<Option<Profile>, String> {
+    match focus::remembered() {
+        Some(window) => find(&window),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::focus::Handle;
+
+    fn window(class: &str, process: &str) -> TargetWindow {
+        TargetWindow {
+            handle: Handle::X11(1),
+            class: Some(class.to_string()),
+            process: Some(process.to_string()),
+            title: None,
+            pid: None,
+        }
+    }
+
+    #[test]
+    fn terminals_paste_with_ctrl_shift_v() {
+        let profiles = default_profiles();
+        let profile = profiles
+            .iter()
+            .find(|p| p.matches(&window("kitty", "kitty")));
+        assert!(profiles[0].matches(&window("Unknown", "ALACRITTY")));
+        assert!(!profiles[0].matches(&window("firefox", "firefox")));
+
+        let (steps, method) = plan(profile, "ls", InputMethod::Paste, None).unwrap();
+        assert_eq!(steps, vec![Step::Text("ls".to_string())]);
+        assert_eq!(method, Method::Paste("Ctrl+Shift+V".parse().unwrap()));
+    }
+
+    #[test]
+    fn chat_profile_breaks_lines_and_sends() {
+        let chat = Profile {
+            name: "Chat".to_string(),
+            processes: vec!["slack".to_string()],
+            input_method: Some(InputMethod::TypeDelayed),
+            typing_delay_ms: Some(5),
+            newlines: Newlines::ShiftEnter,
+            after_insert: vec!["Enter".to_string()],
+            ..Profile::default()
+        };
+        chat.validate().unwrap();
+
+        let (steps, method) = plan(Some(&chat), "hi\nthere", InputMethod::Paste, Some(40)).unwrap();
+        assert_eq!(method, Method::Type(Some(Duration::from_millis(5))));
+        assert_eq!(
+            steps,
+            vec![
+                Step::Text("hi".to_string()),
+                Step::Press("Shift+Enter".parse().unwrap()),
+                Step::Text("there".to_string()),
+                Step::Press(Chord::key(Key::Enter)),
+            ]
+        );
+    }
+}
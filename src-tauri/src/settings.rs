@@ -0,0 +1,149 @@
+// Persistent backend settings
+// Stored as JSON in the app's local data directory
+
+use crate::injection::InjectionConfig;
+use crate::typing::TypingConfig;
+use crate::vad::VadConfig;
+use crate::whisper::TranscribeOptions;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tauri::command;
+
+const SETTINGS_FILE: &str = "settings.json";
+
+/// How the dictation shortcut controls recording
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DictationMode {
+    /// Each press starts or stops dictation
+    #[default]
+    Toggle,
+    /// Dictation runs while the shortcut is held down
+    PushToTalk,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Settings {
+    /// Read-only directory with pre-provisioned Whisper models, searched
+    /// after the local models directory
+    pub shared_models_dir: Option<PathBuf>,
+    /// Defaults for native transcription when a command gets no options
+    pub transcribe: TranscribeOptions,
+    /// Silence trimming and automatic end of recordings
+    pub vad: VadConfig,
+    /// How the dictation shortcut controls recording
+    pub dictation_mode: DictationMode,
+    /// Which text injection backends to try, in order
+    pub injection: InjectionConfig,
+    /// How typing jobs can be stopped
+    pub typing: TypingConfig,
+}
+
+/// Root directory for everything the app stores on disk
+pub fn app_data_dir() -> Result<PathBuf, String> {
+    let data_dir = dirs::data_local_dir()
+        .ok_or_else(|| "Could not find local data directory".to_string())?;
+    let app_dir = data_dir.join("voice-dictation-widget");
+    std::fs::create_dir_all(&app_dir)
+        .map_err(|e| format!("Failed to create data directory: {}", e))?;
+    Ok(app_dir)
+}
+
+fn settings_path() -> Result<PathBuf, String> {
+    Ok(app_data_dir()?.join(SETTINGS_FILE))
+}
+
+/// Load settings, using defaults when the file does not exist yet
+pub fn load() -> Result<Settings, String> {
+    let path = settings_path()?;
+    if !path.exists() {
+        return Ok(Settings::default());
+    }
+
+    let contents = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read settings: {}", e))?;
+    serde_json::from_str(&contents)
+        .map_err(|e| format!("Invalid settings file {}: {}", path.display(), e))
+}
+
+pub fn save(settings: &Settings) -> Result<(), String> {
+    let contents = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    std::fs::write(settings_path()?, contents)
+        .map_err(|e| format!("Failed to write settings: {}", e))
+}
+
+/// Load, modify and save settings in one step
+pub fn update(f: impl FnOnce(&mut Settings)) -> Result<Settings, String> {
+    let mut settings = load()?;
+    f(&mut settings);
+    save(&settings)?;
+    Ok(settings)
+}
+
+#[command]
+pub fn get_settings() -> Result<Settings, String> {
+    load()
+}
+
+/// Point the app at a shared, read-only models directory (or clear it)
+#[command]
+pub fn set_shared_models_dir(path: Option<String>) -> Result<Settings, String> {
+    let path = path.filter(|p| !p.trim().is_empty()).map(PathBuf::from);
+    if let Some(dir) = &path {
+        if !dir.is_dir() {
+            return Err(format!("{} is not a directory", dir.display()));
+        }
+    }
+    update(|s| s.shared_models_dir = path)
+}
+
+#[command]
+pub fn get_transcribe_options() -> Result<TranscribeOptions, String> {
+    Ok(load()?.transcribe)
+}
+
+/// Persist the decoding options used when a command gets none
+#[command]
+pub fn set_transcribe_options(options: TranscribeOptions) -> Result<TranscribeOptions, String> {
+    options.validate()?;
+    Ok(update(|s| s.transcribe = options)?.transcribe)
+}
+
+#[command]
+pub fn get_vad_config() -> Result<VadConfig, String> {
+    Ok(load()?.vad)
+}
+
+/// Persist voice activity detection settings
+#[command]
+pub fn set_vad_config(config: VadConfig) -> Result<VadConfig, String> {
+    config.validate()?;
+    Ok(update(|s| s.vad = config)?.vad)
+}
+
+#[command]
+pub fn get_injection_config() -> Result<InjectionConfig, String> {
+    Ok(load()?.injection)
+}
+
+/// Persist the text injection backends and their fallback order
+#[command]
+pub fn set_injection_config(config: InjectionConfig) -> Result<InjectionConfig, String> {
+    config.validate()?;
+    Ok(update(|s| s.injection = config)?.injection)
+}
+
+#[command]
+pub fn get_typing_config() -> Result<TypingConfig, String> {
+    Ok(load()?.typing)
+}
+
+/// Persist the shortcut that stops typing
+#[command]
+pub fn set_typing_config(config: TypingConfig) -> Result<TypingConfig, String> {
+    config.validate()?;
+    crate::shortcuts::check_abort_shortcut(&config.abort_shortcut)?;
+    Ok(update(|s| s.typing = config)?.typing)
+}
@@ -0,0 +1,381 @@
+// Global shortcuts
+// Widget actions bound to system-wide shortcuts, stored in shortcuts.json and
+// registered at startup. Dictation either toggles on each press or, in
+// push-to-talk mode, runs while the shortcut is held: `start-dictation` on
+// press, `stop-dictation` on release.
+
+use crate::settings::{self, DictationMode};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{command, AppHandle, Emitter, Manager, Runtime};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+const SHORTCUTS_FILE: &str = "shortcuts.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ShortcutAction {
+    /// Start or stop dictation, depending on the dictation mode
+    Dictation,
+    /// Show or hide the widget
+    ToggleWidget,
+    /// Clear the last transcription
+    Delete,
+    /// Copy the last transcription to paste over a selection
+    Replace,
+}
+
+impl ShortcutAction {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Dictation => "dictation",
+            Self::ToggleWidget => "show/hide widget",
+            Self::Delete => "delete",
+            Self::Replace => "replace",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Binding {
+    pub action: ShortcutAction,
+    /// Accelerator such as `CommandOrControl+Shift+D`
+    pub shortcut: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BindingStatus {
+    pub action: ShortcutAction,
+    pub shortcut: String,
+    /// False when the shortcut is invalid or another application holds it
+    pub registered: bool,
+    pub error: Option<String>,
+}
+
+/// Outcome of the last registration, for `list_shortcuts`
+static STATUS: Mutex<Vec<BindingStatus>> = Mutex::new(Vec::new());
+
+fn default_bindings() -> Vec<Binding> {
+    vec![
+        Binding {
+            action: ShortcutAction::Dictation,
+            shortcut: "CommandOrControl+Shift+D".to_string(),
+        },
+        Binding {
+            action: ShortcutAction::ToggleWidget,
+            shortcut: "CommandOrControl+Shift+H".to_string(),
+        },
+    ]
+}
+
+fn shortcuts_path() -> Result<PathBuf, String> {
+    Ok(settings::app_data_dir()?.join(SHORTCUTS_FILE))
+}
+
+/// Load the stored bindings, using the defaults when there are none yet
+pub fn load() -> Result<Vec<Binding>, String> {
+    let path = shortcuts_path()?;
+    if !path.exists() {
+        return Ok(default_bindings());
+    }
+
+    let contents = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read shortcuts: {}", e))?;
+    serde_json::from_str(&contents)
+        .map_err(|e| format!("Invalid shortcuts file {}: {}", path.display(), e))
+}
+
+fn save(bindings: &[Binding]) -> Result<(), String> {
+    let contents = serde_json::to_string_pretty(bindings)
+        .map_err(|e| format!("Failed to serialize shortcuts: {}", e))?;
+    std::fs::write(shortcuts_path()?, contents)
+        .map_err(|e| format!("Failed to write shortcuts: {}", e))
+}
+
+fn parse(shortcut: &str) -> Result<Shortcut, String> {
+    shortcut
+        .parse()
+        .map_err(|e| format!("Invalid shortcut '{}': {}", shortcut, e))
+}
+
+fn on_dictation<R: Runtime>(app: &AppHandle<R>, mode: DictationMode, state: ShortcutState) {
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+    let event = match (mode, state) {
+        (DictationMode::Toggle, ShortcutState::Pressed) => "toggle-dictation",
+        (DictationMode::PushToTalk, ShortcutState::Pressed) => "start-dictation",
+        (DictationMode::PushToTalk, ShortcutState::Released) => "stop-dictation",
+        (DictationMode::Toggle, ShortcutState::Released) => return,
+    };
+    if state == ShortcutState::Pressed {
+        // Before the widget takes focus
+        crate::focus::remember();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+    let _ = window.emit(event, ());
+}
+
+fn on_shortcut<R: Runtime>(
+    app: &AppHandle<R>,
+    action: ShortcutAction,
+    mode: DictationMode,
+    state: ShortcutState,
+) {
+    if action == ShortcutAction::Dictation {
+        on_dictation(app, mode, state);
+        return;
+    }
+    if state != ShortcutState::Pressed {
+        return;
+    }
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+    match action {
+        ShortcutAction::ToggleWidget => {
+            if window.is_visible().unwrap_or(false) {
+                let _ = window.hide();
+            } else {
+                let _ = window.show();
+                let _ = window.set_focus();
+            }
+        }
+        _ => {
+            let _ = window.emit("shortcut-action", action);
+        }
+    }
+}
+
+/// Shortcuts currently registered for a binding
+fn registered_shortcuts() -> Vec<Shortcut> {
+    STATUS
+        .lock()
+        .map(|status| {
+            status
+                .iter()
+                .filter(|s| s.registered)
+                .filter_map(|s| parse(&s.shortcut).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Whether `shortcut` is registered for a binding
+pub fn is_bound(shortcut: Shortcut) -> bool {
+    registered_shortcuts().contains(&shortcut)
+}
+
+/// Replace the registered shortcuts with `bindings`. Shortcuts registered
+/// elsewhere, such as the typing abort shortcut, are left alone.
+fn apply<R: Runtime>(app: &AppHandle<R>, bindings: &[Binding]) -> Vec<BindingStatus> {
+    let global = app.global_shortcut();
+    for shortcut in registered_shortcuts() {
+        let _ = global.unregister(shortcut);
+    }
+    let mode = settings::load()
+        .map(|s| s.dictation_mode)
+        .unwrap_or_default();
+
+    let statuses: Vec<BindingStatus> = bindings
+        .iter()
+        .map(|binding| {
+            let action = binding.action;
+            let result = parse(&binding.shortcut).and_then(|shortcut| {
+                global
+                    .on_shortcut(shortcut, move |app, _shortcut, event| {
+                        on_shortcut(app, action, mode, event.state())
+                    })
+                    .map_err(|e| format!("Failed to register {}: {}", binding.shortcut, e))
+            });
+            BindingStatus {
+                action,
+                shortcut: binding.shortcut.clone(),
+                registered: result.is_ok(),
+                error: result.err(),
+            }
+        })
+        .collect();
+
+    if let Ok(mut status) = STATUS.lock() {
+        *status = statuses.clone();
+    }
+    statuses
+}
+
+/// Reject bindings that use the same shortcut twice, or the shortcut that
+/// stops typing
+fn check_conflicts(bindings: &[Binding], abort_shortcut: &str) -> Result<(), String> {
+    let parsed = bindings
+        .iter()
+        .map(|b| parse(&b.shortcut))
+        .collect::<Result<Vec<_>, _>>()?;
+    if let Ok(abort) = parse(abort_shortcut) {
+        if let Some(i) = parsed.iter().position(|shortcut| *shortcut == abort) {
+            return Err(format!(
+                "{} is already used to stop typing",
+                bindings[i].shortcut
+            ));
+        }
+    }
+    for (i, shortcut) in parsed.iter().enumerate() {
+        if let Some(j) = parsed[..i].iter().position(|other| other == shortcut) {
+            return Err(format!(
+                "{} is already bound to {}",
+                bindings[i].shortcut,
+                bindings[j].action.label()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Register and save new bindings. When `added` can't be registered, for
+/// example because another application holds it, nothing changes.
+fn update<R: Runtime>(
+    app: &AppHandle<R>,
+    bindings: Vec<Binding>,
+    added: Option<&str>,
+) -> Result<Vec<BindingStatus>, String> {
+    check_conflicts(&bindings, &settings::load()?.typing.abort_shortcut)?;
+    let previous = load()?;
+
+    let statuses = apply(app, &bindings);
+    if let Some(failed) = statuses
+        .iter()
+        .find(|s| !s.registered && Some(s.shortcut.as_str()) == added)
+    {
+        let error = failed.error.clone().unwrap_or_default();
+        apply(app, &previous);
+        return Err(error);
+    }
+
+    save(&bindings)?;
+    Ok(statuses)
+}
+
+/// Reject an abort shortcut that a binding already uses
+pub fn check_abort_shortcut(abort_shortcut: &str) -> Result<(), String> {
+    check_conflicts(&load()?, abort_shortcut)
+}
+
+/// Register the saved shortcuts. Shortcuts taken by other applications are
+/// skipped and reported by `list_shortcuts`.
+pub fn register_all<R: Runtime>(app: &AppHandle<R>) -> Result<(), String> {
+    apply(app, &load()?);
+    Ok(())
+}
+
+/// Every binding and whether it is registered
+#[command]
+pub fn list_shortcuts() -> Result<Vec<BindingStatus>, String> {
+    Ok(STATUS.lock().map_err(|_| "Lock poisoned")?.clone())
+}
+
+/// Bind an additional shortcut to an action
+#[command]
+pub fn register_shortcut<R: Runtime>(
+    app: AppHandle<R>,
+    action: ShortcutAction,
+    shortcut: String,
+) -> Result<Vec<BindingStatus>, String> {
+    let mut bindings = load()?;
+    bindings.push(Binding {
+        action,
+        shortcut: shortcut.clone(),
+    });
+    update(&app, bindings, Some(&shortcut))
+}
+
+/// Remove a shortcut from whatever action it is bound to
+#[command]
+pub fn unregister_shortcut<R: Runtime>(
+    app: AppHandle<R>,
+    shortcut: String,
+) -> Result<Vec<BindingStatus>, String> {
+    let target = parse(&shortcut)?;
+    let mut bindings = load()?;
+    let count = bindings.len();
+    bindings.retain(|b| parse(&b.shortcut).ok() != Some(target));
+    if bindings.len() == count {
+        return Err(format!("{} is not bound to any action", shortcut));
+    }
+    update(&app, bindings, None)
+}
+
+/// Bind an action to `shortcut` alone, replacing its current shortcuts
+#[command]
+pub fn rebind_shortcut<R: Runtime>(
+    app: AppHandle<R>,
+    action: ShortcutAction,
+    shortcut: String,
+) -> Result<Vec<BindingStatus>, String> {
+    let mut bindings = load()?;
+    bindings.retain(|b| b.action != action);
+    bindings.push(Binding {
+        action,
+        shortcut: shortcut.clone(),
+    });
+    update(&app, bindings, Some(&shortcut))
+}
+
+#[command]
+pub fn reset_shortcuts<R: Runtime>(app: AppHandle<R>) -> Result<Vec<BindingStatus>, String> {
+    update(&app, default_bindings(), None)
+}
+
+/// Choose whether the dictation shortcut toggles dictation or works as
+/// push-to-talk
+#[command]
+pub fn set_dictation_mode<R: Runtime>(
+    app: AppHandle<R>,
+    mode: DictationMode,
+) -> Result<DictationMode, String> {
+    let mode = settings::update(|s| s.dictation_mode = mode)?.dictation_mode;
+    // Handlers capture the mode when they are registered
+    apply(&app, &load()?);
+    Ok(mode)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binding(action: ShortcutAction, shortcut: &str) -> Binding {
+        Binding {
+            action,
+            shortcut: shortcut.to_string(),
+        }
+    }
+
+    #[test]
+    fn accepts_distinct_shortcuts() {
+        assert!(check_conflicts(&default_bindings(), "Escape").is_ok());
+    }
+
+    #[test]
+    fn rejects_a_shortcut_bound_twice() {
+        let mut bindings = default_bindings();
+        bindings.push(binding(ShortcutAction::Delete, "Shift+CommandOrControl+D"));
+        let error = check_conflicts(&bindings, "Escape").unwrap_err();
+        assert!(error.contains("already bound to dictation"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_the_abort_shortcut() {
+        let mut bindings = default_bindings();
+        bindings.push(binding(ShortcutAction::Replace, "Escape"));
+        let error = check_conflicts(&bindings, "Escape").unwrap_err();
+        assert!(error.contains("stop typing"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_invalid_shortcuts() {
+        let bindings = vec![binding(ShortcutAction::Delete, "Ctrl+Nope")];
+        assert!(check_conflicts(&bindings, "Escape").is_err());
+    }
+}
@@ -0,0 +1,296 @@
+// Typing jobs
+// Text is sent in chunks of a few characters on a blocking thread, with a
+// `typing-progress` event after each one. A job stops between chunks when
+// `cancel_typing` is called or the abort shortcut is pressed. That shortcut
+// is only registered while something is being typed, so Escape keeps
+// working normally the rest of the time. When it can't be registered,
+// `typing-abort-unavailable` says why.
+
+use crate::injection::{self, Chord, Method, Step};
+use crate::settings;
+use crate::shortcuts;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{command, AppHandle, Emitter, Runtime};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+/// Characters typed between checks for cancellation
+const CHUNK_CHARS: usize = 8;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TypingConfig {
+    /// Global shortcut that stops typing, held only while typing
+    pub abort_shortcut: String,
+}
+
+impl Default for TypingConfig {
+    fn default() -> Self {
+        Self {
+            abort_shortcut: "Escape".to_string(),
+        }
+    }
+}
+
+impl TypingConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        self.abort_shortcut
+            .parse::<Shortcut>()
+            .map(|_| ())
+            .map_err(|e| format!("Invalid shortcut '{}': {}", self.abort_shortcut, e))
+    }
+}
+
+/// Text being typed
+struct TypingJob {
+    id: u64,
+    total: usize,
+    delivered: AtomicUsize,
+    cancelled: AtomicBool,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProgressEvent {
+    job_id: u64,
+    delivered: usize,
+    total: usize,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AbortUnavailableEvent {
+    job_id: u64,
+    error: String,
+}
+
+/// How a typing job ended
+pub struct Outcome {
+    /// The backend that typed the text, or why it stopped
+    pub result: Result<&'static str, String>,
+    /// Characters that reached the target application
+    pub delivered: usize,
+}
+
+static CURRENT: Mutex<Option<Arc<TypingJob>>> = Mutex::new(None);
+static NEXT_JOB_ID: AtomicU64 = AtomicU64::new(1);
+
+impl TypingJob {
+    /// Make a job current. Only one job types at a time.
+    fn start(total: usize) -> Result<Arc<Self>, String> {
+        let mut current = CURRENT.lock().map_err(|_| "Lock poisoned")?;
+        if current.is_some() {
+            return Err("Already typing, cancel that first".to_string());
+        }
+        let job = Arc::new(Self {
+            id: NEXT_JOB_ID.fetch_add(1, Ordering::Relaxed),
+            total,
+            delivered: AtomicUsize::new(0),
+            cancelled: AtomicBool::new(false),
+        });
+        *current = Some(job.clone());
+        Ok(job)
+    }
+
+    fn finish(&self) {
+        if let Ok(mut current) = CURRENT.lock() {
+            if current.as_ref().is_some_and(|job| job.id == self.id) {
+                *current = None;
+            }
+        }
+    }
+
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    fn delivered(&self) -> usize {
+        self.delivered.load(Ordering::Relaxed)
+    }
+
+    /// Count a step that was sent. Fails when the job has been cancelled,
+    /// which stops it before the next step.
+    fn sent<R: Runtime>(&self, app: &AppHandle<R>, step: &Step) -> Result<(), String> {
+        if let Step::Text(text) = step {
+            let count = text.chars().count();
+            let delivered = self.delivered.fetch_add(count, Ordering::Relaxed) + count;
+            let _ = app.emit(
+                "typing-progress",
+                ProgressEvent {
+                    job_id: self.id,
+                    delivered,
+                    total: self.total,
+                },
+            );
+        }
+        if self.cancelled.load(Ordering::Relaxed) {
+            return Err(format!(
+                "Typing cancelled after {} of {} characters",
+                self.delivered(),
+                self.total
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn char_count(steps: &[Step]) -> usize {
+    steps
+        .iter()
+        .map(|step| match step {
+            Step::Text(text) => text.chars().count(),
+            Step::Press(_) => 0,
+        })
+        .sum()
+}
+
+/// Split the text of `steps` into chunks of at most `size` characters
+fn chunk(steps: &[Step], size: usize) -> Vec<Step> {
+    let mut chunked = Vec::new();
+    for step in steps {
+        match step {
+            Step::Text(text) => {
+                let chars: Vec<char> = text.chars().collect();
+                chunked.extend(
+                    chars
+                        .chunks(size)
+                        .map(|chunk| Step::Text(chunk.iter().collect())),
+                );
+            }
+            Step::Press(_) => chunked.push(step.clone()),
+        }
+    }
+    chunked
+}
+
+/// The abort shortcut, unregistered when dropped
+struct AbortShortcut<R: Runtime> {
+    app: AppHandle<R>,
+    shortcut: Shortcut,
+}
+
+impl<R: Runtime> Drop for AbortShortcut<R> {
+    fn drop(&mut self) {
+        // Bindings never take it over while it is registered, but don't
+        // pull a binding's shortcut out from under it if one did
+        if !shortcuts::is_bound(self.shortcut) {
+            let _ = self.app.global_shortcut().unregister(self.shortcut);
+        }
+    }
+}
+
+/// Register the abort shortcut for `job`. Fails when the shortcut is
+/// already taken, or when `steps` press it themselves.
+fn register_abort<R: Runtime>(
+    app: &AppHandle<R>,
+    job: &Arc<TypingJob>,
+    steps: &[Step],
+) -> Result<AbortShortcut<R>, String> {
+    let accelerator = settings::load()?.typing.abort_shortcut;
+    if let Ok(chord) = accelerator.parse::<Chord>() {
+        if steps.contains(&Step::Press(chord)) {
+            return Err(format!(
+                "{} is pressed by the text being typed",
+                accelerator
+            ));
+        }
+    }
+    let shortcut: Shortcut = accelerator
+        .parse()
+        .map_err(|e| format!("Invalid shortcut '{}': {}", accelerator, e))?;
+    let job = job.clone();
+    app.global_shortcut()
+        .on_shortcut(shortcut, move |_app, _shortcut, event| {
+            if event.state() == ShortcutState::Pressed {
+                job.cancel();
+            }
+        })
+        .map_err(|e| format!("Failed to register {}: {}", accelerator, e))?;
+    Ok(AbortShortcut {
+        app: app.clone(),
+        shortcut,
+    })
+}
+
+fn run_job<R: Runtime>(
+    app: &AppHandle<R>,
+    job: &TypingJob,
+    steps: &[Step],
+    method: Method,
+) -> Result<&'static str, String> {
+    // A paste is one action and can't be split
+    let steps = match method {
+        Method::Type(_) => chunk(steps, CHUNK_CHARS),
+        Method::Paste(_) => steps.to_vec(),
+    };
+    injection::inject(app, &steps, method, |step| job.sent(app, step))
+}
+
+/// Send `steps` to the focused window as a cancellable typing job
+pub async fn run<R: Runtime>(app: AppHandle<R>, steps: Vec<Step>, method: Method) -> Outcome {
+    let job = match TypingJob::start(char_count(&steps)) {
+        Ok(job) => job,
+        Err(e) => {
+            return Outcome {
+                result: Err(e),
+                delivered: 0,
+            }
+        }
+    };
+
+    let thread_job = job.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let _abort = register_abort(&app, &thread_job, &steps).inspect_err(|e| {
+            let _ = app.emit(
+                "typing-abort-unavailable",
+                AbortUnavailableEvent {
+                    job_id: thread_job.id,
+                    error: e.clone(),
+                },
+            );
+        });
+        run_job(&app, &thread_job, &steps, method)
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Typing failed: {}", e)));
+    job.finish();
+
+    Outcome {
+        result,
+        delivered: job.delivered(),
+    }
+}
+
+/// Stop the text being typed after the current chunk
+#[command]
+pub fn cancel_typing() -> Result<u64, String> {
+    let current = CURRENT.lock().map_err(|_| "Lock poisoned")?;
+    let job = current
+        .as_ref()
+        .ok_or_else(|| "Nothing is being typed".to_string())?;
+    job.cancel();
+    Ok(job.id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::injection::Key;
+
+    #[test]
+    fn chunks_text_and_keeps_key_presses() {
+        let enter = Step::Press(Chord::key(Key::Enter));
+        let steps = vec![Step::Text("héllo wörld".to_string()), enter.clone()];
+        assert_eq!(char_count(&steps), 11);
+        assert_eq!(
+            chunk(&steps, 4),
+            vec![
+                Step::Text("héll".to_string()),
+                Step::Text("o wö".to_string()),
+                Step::Text("rld".to_string()),
+                enter,
+            ]
+        );
+    }
+}
@@ -0,0 +1,509 @@
+// Voice activity detection
+// Frames of 16kHz mono audio are classified by their energy above a
+// tracked noise floor, with the zero-crossing rate catching quiet fricatives
+// such as "s" and "f". Used to trim silence Whisper would otherwise fill with made-up
+// text, to split long audio at pauses and to end recordings automatically.
+
+#![cfg_attr(not(feature = "native-whisper"), allow(dead_code))]
+
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+const FRAME_MS: usize = 30;
+const FRAME_SAMPLES: usize = crate::audio::WHISPER_SAMPLE_RATE as usize * FRAME_MS / 1000;
+/// How fast the noise floor may rise, so a fan switching on is learned in
+/// seconds while speech hardly moves it. It falls instantly.
+const NOISE_RISE_DB_PER_SEC: f32 = 3.0;
+/// Frames quieter than this are never speech, however quiet the room
+const MIN_SPEECH_DB: f32 = -60.0;
+/// Frames held back at the start of a stream to measure the noise before
+/// anything is classified (300 ms)
+const CALIBRATION_FRAMES: usize = 10;
+/// Fraction of sign changes above which a frame sounds like a fricative
+const FRICATIVE_ZCR: f32 = 0.3;
+/// Speech must last this long to count, so clicks and bumps are ignored
+const MIN_SPEECH_FRAMES: usize = 3;
+/// Silence kept around trimmed speech so word onsets and endings survive
+const TRIM_PADDING_MS: usize = 200;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct VadConfig {
+    /// Cut leading and trailing silence before transcribing
+    pub trim_silence: bool,
+    /// End a recording after this much silence, 0 never ends it. Silence
+    /// before the first speech counts too, so a recording nobody speaks
+    /// into ends on its own.
+    pub silence_timeout_ms: u32,
+    /// Silence that ends a stretch of speech
+    pub min_pause_ms: u32,
+    /// How far above the noise floor speech has to be. Lower values pick up
+    /// quieter speech but also more background noise.
+    pub threshold_db: f32,
+}
+
+impl Default for VadConfig {
+    fn default() -> Self {
+        Self {
+            trim_silence: true,
+            silence_timeout_ms: 5000,
+            min_pause_ms: 600,
+            threshold_db: 10.0,
+        }
+    }
+}
+
+impl VadConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(3.0..=40.0).contains(&self.threshold_db) {
+            return Err("VAD threshold must be between 3 and 40 dB".to_string());
+        }
+        if !(FRAME_MS as u32 * 3..=10_000).contains(&self.min_pause_ms) {
+            return Err("Minimum pause must be between 90 and 10000 ms".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VadEvent {
+    SpeechStart { at_ms: u64 },
+    SpeechEnd { at_ms: u64 },
+}
+
+/// Streaming detector fed with 16kHz mono audio in blocks of any size
+pub struct Vad {
+    threshold_db: f32,
+    min_pause_frames: usize,
+    /// Samples that don't fill a frame yet
+    pending: Vec<f32>,
+    noise_db: f32,
+    /// Frames waiting for the noise to be measured, `None` once it is
+    calibration: Option<Vec<Vec<f32>>>,
+    frames: u64,
+    speech_run: usize,
+    silence_run: usize,
+    in_speech: bool,
+    last_speech_frame: Option<u64>,
+}
+
+fn frames_to_ms(frames: u64) -> u64 {
+    frames * FRAME_MS as u64
+}
+
+fn ms_to_samples(ms: u64) -> usize {
+    ms as usize * FRAME_SAMPLES / FRAME_MS
+}
+
+fn energy_db(frame: &[f32]) -> f32 {
+    let power = frame.iter().map(|s| s * s).sum::<f32>() / frame.len().max(1) as f32;
+    10.0 * (power + 1e-10).log10()
+}
+
+fn zero_crossing_rate(frame: &[f32]) -> f32 {
+    let crossings = frame
+        .windows(2)
+        .filter(|w| (w[0] >= 0.0) != (w[1] >= 0.0))
+        .count();
+    crossings as f32 / frame.len().max(1) as f32
+}
+
+impl Vad {
+    pub fn new(config: &VadConfig) -> Self {
+        Self {
+            threshold_db: config.threshold_db,
+            min_pause_frames: (config.min_pause_ms as usize / FRAME_MS).max(1),
+            pending: Vec::with_capacity(FRAME_SAMPLES),
+            noise_db: MIN_SPEECH_DB,
+            calibration: Some(Vec::with_capacity(CALIBRATION_FRAMES)),
+            frames: 0,
+            speech_run: 0,
+            silence_run: 0,
+            in_speech: false,
+            last_speech_frame: None,
+        }
+    }
+
+    /// Analyse the next block of audio
+    pub fn push(&mut self, mut audio: &[f32]) -> Vec<VadEvent> {
+        let mut events = Vec::new();
+
+        // Complete the frame left over from the previous block first
+        if !self.pending.is_empty() {
+            let take = (FRAME_SAMPLES - self.pending.len()).min(audio.len());
+            self.pending.extend_from_slice(&audio[..take]);
+            audio = &audio[take..];
+            if self.pending.len() < FRAME_SAMPLES {
+                return events;
+            }
+            let frame = std::mem::take(&mut self.pending);
+            self.process(&frame, &mut events);
+        }
+
+        let mut frames = audio.chunks_exact(FRAME_SAMPLES);
+        for frame in &mut frames {
+            self.process(frame, &mut events);
+        }
+        self.pending.extend_from_slice(frames.remainder());
+        events
+    }
+
+    /// Classify frames still held back for calibration and end speech that
+    /// is still going when the audio ends
+    pub fn finish(&mut self) -> Vec<VadEvent> {
+        let mut events = Vec::new();
+        self.calibrate(&mut events);
+        if self.in_speech {
+            self.in_speech = false;
+            let end = self.last_speech_frame.map(|f| f + 1).unwrap_or(self.frames);
+            events.push(VadEvent::SpeechEnd {
+                at_ms: frames_to_ms(end),
+            });
+        }
+        events
+    }
+
+    /// Time since speech was last heard, or since the start if it never was.
+    /// Counting from the start lets a recording nobody speaks into time out.
+    pub fn silence_ms(&self) -> u64 {
+        if self.in_speech && self.silence_run == 0 {
+            return 0;
+        }
+        let since = self.last_speech_frame.map(|f| f + 1).unwrap_or(0);
+        frames_to_ms(self.frames - since)
+    }
+
+    fn process(&mut self, frame: &[f32], events: &mut Vec<VadEvent>) {
+        if let Some(calibration) = &mut self.calibration {
+            calibration.push(frame.to_vec());
+            if calibration.len() >= CALIBRATION_FRAMES {
+                self.calibrate(events);
+            }
+            return;
+        }
+        let is_speech = self.classify(frame);
+        events.extend(self.advance(is_speech));
+    }
+
+    /// Seed the noise floor from the quietest held-back frame, then classify
+    /// them. Starting from `MIN_SPEECH_DB` instead would take the slowly
+    /// rising floor seconds to catch up with a noisy room, hearing speech in
+    /// the meantime. Speech filling the whole window sets the floor too high
+    /// until the first pause, where it falls straight back.
+    fn calibrate(&mut self, events: &mut Vec<VadEvent>) {
+        let Some(frames) = self.calibration.take() else {
+            return;
+        };
+        if let Some(noise) = frames.iter().map(|f| energy_db(f)).min_by(f32::total_cmp) {
+            self.noise_db = noise.max(MIN_SPEECH_DB);
+        }
+        for frame in &frames {
+            let is_speech = self.classify(frame);
+            events.extend(self.advance(is_speech));
+        }
+    }
+
+    fn classify(&mut self, frame: &[f32]) -> bool {
+        let energy = energy_db(frame);
+        let noise = self.noise_db;
+        let rise = NOISE_RISE_DB_PER_SEC * FRAME_MS as f32 / 1000.0;
+        self.noise_db = (noise + rise).min(energy);
+
+        energy > MIN_SPEECH_DB
+            && (energy > noise + self.threshold_db
+                || (zero_crossing_rate(frame) > FRICATIVE_ZCR
+                    && energy > noise + self.threshold_db / 2.0))
+    }
+
+    fn advance(&mut self, is_speech: bool) -> Option<VadEvent> {
+        let index = self.frames;
+        self.frames += 1;
+
+        if is_speech {
+            self.speech_run += 1;
+            self.silence_run = 0;
+            if self.in_speech {
+                self.last_speech_frame = Some(index);
+            } else if self.speech_run >= MIN_SPEECH_FRAMES {
+                self.in_speech = true;
+                self.last_speech_frame = Some(index);
+                let start = index + 1 - self.speech_run as u64;
+                return Some(VadEvent::SpeechStart {
+                    at_ms: frames_to_ms(start),
+                });
+            }
+        } else {
+            self.speech_run = 0;
+            self.silence_run += 1;
+            if self.in_speech && self.silence_run >= self.min_pause_frames {
+                self.in_speech = false;
+                let end = index + 1 - self.silence_run as u64;
+                return Some(VadEvent::SpeechEnd {
+                    at_ms: frames_to_ms(end),
+                });
+            }
+        }
+        None
+    }
+}
+
+/// Noise level of recorded audio, taken from its quieter frames
+fn noise_floor(audio: &[f32]) -> Option<f32> {
+    let mut energies: Vec<f32> = audio.chunks_exact(FRAME_SAMPLES).map(energy_db).collect();
+    if energies.is_empty() {
+        return None;
+    }
+    energies.sort_by(f32::total_cmp);
+    Some(energies[energies.len() / 10])
+}
+
+/// Sample ranges that contain speech
+pub fn speech_ranges(audio: &[f32], config: &VadConfig) -> Vec<Range<usize>> {
+    let mut vad = Vad::new(config);
+    // With all the audio at hand the noise is known up front, so speech
+    // right at the start is told apart from a noisy room
+    if let Some(noise) = noise_floor(audio) {
+        vad.noise_db = noise.max(MIN_SPEECH_DB);
+        vad.calibration = None;
+    }
+    let mut events = vad.push(audio);
+    events.extend(vad.finish());
+
+    let mut ranges = Vec::new();
+    let mut start = None;
+    for event in events {
+        match event {
+            VadEvent::SpeechStart { at_ms } => start = Some(ms_to_samples(at_ms)),
+            VadEvent::SpeechEnd { at_ms } => {
+                if let Some(start) = start.take() {
+                    ranges.push(start..ms_to_samples(at_ms).min(audio.len()));
+                }
+            }
+        }
+    }
+    ranges
+}
+
+/// The part of `audio` from the first to the last speech, with a little
+/// padding, or `None` if nobody speaks
+pub fn trim_silence(audio: &[f32], config: &VadConfig) -> Option<Range<usize>> {
+    let ranges = speech_ranges(audio, config);
+    let padding = ms_to_samples(TRIM_PADDING_MS as u64);
+    let start = ranges.first()?.start.saturating_sub(padding);
+    let end = (ranges.last()?.end + padding).min(audio.len());
+    Some(start..end)
+}
+
+/// A piece of a longer recording that is transcribed on its own
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk {
+    /// Samples to transcribe
+    pub range: Range<usize>,
+    /// Samples this chunk is responsible for. Narrower than `range` where a
+    /// cut misses a pause and the chunk overlaps its neighbour; text centred
+    /// outside it belongs to the neighbour.
+    pub keep: Range<usize>,
+}
+
+/// Split audio into chunks of at most `max_len` samples, cutting in the
+/// longest pause of each chunk's second half. Where there is no pause the
+/// cut goes in the quietest frame and the chunks on either side overlap by
+/// `overlap` samples, so a word split by the cut is heard whole by one of them.
+pub fn split_at_pauses(
+    audio: &[f32],
+    max_len: usize,
+    overlap: usize,
+    config: &VadConfig,
+) -> Vec<Chunk> {
+    let overlap = overlap.min(max_len / 2);
+    let span = (max_len - overlap).max(FRAME_SAMPLES * 2);
+    let speech = speech_ranges(audio, config);
+    let mut pauses = Vec::with_capacity(speech.len() + 1);
+    let mut previous_end = 0;
+    for range in &speech {
+        pauses.push(previous_end..range.start);
+        previous_end = range.end;
+    }
+    pauses.push(previous_end..audio.len());
+
+    // Each cut and how far the chunks reach across it
+    let mut cuts = Vec::new();
+    let mut start = 0;
+    while audio.len() - start > span {
+        let window = start + span / 2..start + span;
+
+        let longest_pause = pauses
+            .iter()
+            .map(|p| p.start.max(window.start)..p.end.min(window.end))
+            .filter(|p| p.start < p.end)
+            .max_by_key(|p| p.len());
+        let (cut, reach) = match longest_pause {
+            Some(pause) => ((pause.start + pause.end) / 2, 0),
+            None => {
+                let first_frame = window.start.div_ceil(FRAME_SAMPLES);
+                let last_frame = window.end / FRAME_SAMPLES;
+                let cut = (first_frame..last_frame)
+                    .min_by(|&a, &b| {
+                        let energy = |f: usize| {
+                            energy_db(&audio[f * FRAME_SAMPLES..(f + 1) * FRAME_SAMPLES])
+                        };
+                        energy(a).total_cmp(&energy(b))
+                    })
+                    .map(|f| f * FRAME_SAMPLES + FRAME_SAMPLES / 2)
+                    .unwrap_or(window.end);
+                (cut, overlap / 2)
+            }
+        };
+
+        cuts.push((cut, reach));
+        start = cut;
+    }
+
+    let mut chunks = Vec::with_capacity(cuts.len() + 1);
+    let (mut start, mut start_reach) = (0, 0);
+    for (end, end_reach) in cuts.into_iter().chain([(audio.len(), 0)]) {
+        chunks.push(Chunk {
+            range: start - start_reach..(end + end_reach).min(audio.len()),
+            keep: start..end,
+        });
+        (start, start_reach) = (end, end_reach);
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: usize = 16000;
+
+    /// Quiet, deterministic background noise
+    fn noise(len: usize, seed: &mut u32) -> Vec<f32> {
+        (0..len)
+            .map(|_| {
+                *seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+                (*seed >> 8) as f32 / (1u32 << 24) as f32 * 0.002 - 0.001
+            })
+            .collect()
+    }
+
+    /// Alternating noise and tone bursts, lengths in milliseconds
+    fn signal(parts: &[(usize, bool)]) -> Vec<f32> {
+        let mut seed = 1;
+        let mut audio = Vec::new();
+        for &(ms, voiced) in parts {
+            let mut part = noise(ms * RATE / 1000, &mut seed);
+            if voiced {
+                for (i, sample) in part.iter_mut().enumerate() {
+                    *sample +=
+                        0.3 * (2.0 * std::f32::consts::PI * 220.0 * i as f32 / RATE as f32).sin();
+                }
+            }
+            audio.extend(part);
+        }
+        audio
+    }
+
+    fn ms(samples: usize) -> i64 {
+        (samples * 1000 / RATE) as i64
+    }
+
+    #[test]
+    fn finds_speech_between_silence() {
+        let audio = signal(&[(1000, false), (1000, true), (1000, false)]);
+        let ranges = speech_ranges(&audio, &VadConfig::default());
+        assert_eq!(ranges.len(), 1, "{:?}", ranges);
+        assert!((ms(ranges[0].start) - 1000).abs() <= 2 * FRAME_MS as i64);
+        assert!((ms(ranges[0].end) - 2000).abs() <= 2 * FRAME_MS as i64);
+    }
+
+    #[test]
+    fn hears_speech_from_the_first_frame() {
+        let audio = signal(&[(1000, true), (1000, false)]);
+        let ranges = speech_ranges(&audio, &VadConfig::default());
+        assert_eq!(ranges.len(), 1, "{:?}", ranges);
+        assert_eq!(ranges[0].start, 0);
+    }
+
+    #[test]
+    fn noise_alone_is_not_speech() {
+        let audio = noise(RATE * 3, &mut 7);
+        assert!(speech_ranges(&audio, &VadConfig::default()).is_empty());
+        assert_eq!(trim_silence(&audio, &VadConfig::default()), None);
+    }
+
+    #[test]
+    fn trims_to_speech_with_padding() {
+        let audio = signal(&[(1500, false), (500, true), (2000, false)]);
+        let range = trim_silence(&audio, &VadConfig::default()).unwrap();
+        assert!((ms(range.start) - 1300).abs() <= 2 * FRAME_MS as i64);
+        assert!((ms(range.end) - 2200).abs() <= 2 * FRAME_MS as i64);
+    }
+
+    #[test]
+    fn reports_start_end_and_silence_when_streaming() {
+        let audio = signal(&[(500, false), (1000, true), (1500, false)]);
+        let mut vad = Vad::new(&VadConfig::default());
+        let mut events = Vec::new();
+        for block in audio.chunks(1234) {
+            events.extend(vad.push(block));
+        }
+        assert!(matches!(
+            events[..],
+            [VadEvent::SpeechStart { .. }, VadEvent::SpeechEnd { .. }]
+        ));
+        assert!((vad.silence_ms() as i64 - 1500).abs() <= 2 * FRAME_MS as i64);
+    }
+
+    #[test]
+    fn measures_a_noisy_room_before_listening() {
+        // Steady noise far above the level the floor would otherwise start at
+        let audio: Vec<f32> = noise(RATE * 3, &mut 3).iter().map(|s| s * 20.0).collect();
+        let mut vad = Vad::new(&VadConfig::default());
+        let mut events = Vec::new();
+        for block in audio.chunks(1234) {
+            events.extend(vad.push(block));
+        }
+        events.extend(vad.finish());
+        assert!(events.is_empty(), "{:?}", events);
+        assert!((vad.silence_ms() as i64 - 3000).abs() <= 2 * FRAME_MS as i64);
+    }
+
+    #[test]
+    fn splits_in_pauses() {
+        let audio = signal(&[
+            (8000, true),
+            (1000, false),
+            (8000, true),
+            (1000, false),
+            (8000, true),
+        ]);
+        let chunks = split_at_pauses(&audio, RATE * 20, RATE * 2, &VadConfig::default());
+        assert_eq!(chunks.len(), 2, "{:?}", chunks);
+        assert!(
+            (17000..18000).contains(&ms(chunks[0].range.end)),
+            "{:?}",
+            chunks
+        );
+        assert_eq!(chunks[1].range.end, audio.len());
+        // Cuts in a pause need no overlap
+        assert!(chunks.iter().all(|c| c.range == c.keep));
+        assert_eq!(chunks[0].range.end, chunks[1].range.start);
+    }
+
+    #[test]
+    fn overlaps_cuts_outside_pauses() {
+        // Speech from 6 s on, so the first cut lands in the middle of it
+        let audio = signal(&[(6000, false), (40000, true)]);
+        let chunks = split_at_pauses(&audio, RATE * 20, RATE * 2, &VadConfig::default());
+        assert_eq!(chunks[0].keep.start, 0);
+        assert_eq!(chunks.last().unwrap().keep.end, audio.len());
+        assert_eq!(chunks[0].range.end - chunks[1].range.start, RATE * 2);
+        for pair in chunks.windows(2) {
+            assert_eq!(pair[0].keep.end, pair[1].keep.start);
+            let overlap = pair[0].range.end - pair[1].range.start;
+            assert!(overlap == 0 || overlap == RATE * 2, "{:?}", pair);
+        }
+        assert!(chunks.iter().all(|c| c.range.len() <= RATE * 20));
+    }
+}
@@ -0,0 +1,121 @@
+// User vocabulary
+// Product names, people and jargon that Whisper should prefer. The words
+// are fed into the decoder's initial prompt on every native transcription.
+
+use std::path::PathBuf;
+use tauri::command;
+
+const VOCABULARY_FILE: &str = "vocabulary.json";
+
+fn vocabulary_path() -> Result<PathBuf, String> {
+    Ok(crate::settings::app_data_dir()?.join(VOCABULARY_FILE))
+}
+
+/// Load the stored words, in the order they were added
+pub fn load() -> Result<Vec<String>, String> {
+    let path = vocabulary_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let contents = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read vocabulary: {}", e))?;
+    serde_json::from_str(&contents)
+        .map_err(|e| format!("Invalid vocabulary file {}: {}", path.display(), e))
+}
+
+fn save(words: &[String]) -> Result<(), String> {
+    let contents = serde_json::to_string_pretty(words)
+        .map_err(|e| format!("Failed to serialize vocabulary: {}", e))?;
+    std::fs::write(vocabulary_path()?, contents)
+        .map_err(|e| format!("Failed to write vocabulary: {}", e))
+}
+
+/// Build a prompt from the words that fit in `token_budget`. A word too long
+/// for what is left is skipped, so shorter words after it still get in.
+/// `count_tokens` returns the token length of a prompt fragment.
+#[cfg_attr(not(feature = "native-whisper"), allow(dead_code))]
+pub fn build_prompt(
+    words: &[String],
+    token_budget: usize,
+    count_tokens: impl Fn(&str) -> usize,
+) -> String {
+    let mut used = 0;
+    let mut included = Vec::new();
+
+    for word in words {
+        let cost = count_tokens(&format!(" {},", word));
+        if cost > token_budget - used {
+            continue;
+        }
+        used += cost;
+        included.push(word.as_str());
+    }
+
+    if included.is_empty() {
+        String::new()
+    } else {
+        format!("{}.", included.join(", "))
+    }
+}
+
+#[command]
+pub fn list_vocabulary() -> Result<Vec<String>, String> {
+    load()
+}
+
+/// Add a word or phrase; duplicates (ignoring case) are skipped
+#[command]
+pub fn add_vocabulary_word(word: String) -> Result<Vec<String>, String> {
+    let word = word.trim().to_string();
+    if word.is_empty() {
+        return Err("Word is empty".to_string());
+    }
+
+    let mut words = load()?;
+    if !words.iter().any(|w| w.eq_ignore_ascii_case(&word)) {
+        words.push(word);
+        save(&words)?;
+    }
+    Ok(words)
+}
+
+#[command]
+pub fn remove_vocabulary_word(word: String) -> Result<Vec<String>, String> {
+    let mut words = load()?;
+    words.retain(|w| !w.eq_ignore_ascii_case(word.trim()));
+    save(&words)?;
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One token per character, so word lengths decide what fits
+    fn count_chars(text: &str) -> usize {
+        text.chars().count()
+    }
+
+    fn words(words: &[&str]) -> Vec<String> {
+        words.iter().map(|w| w.to_string()).collect()
+    }
+
+    #[test]
+    fn skips_words_that_no_longer_fit() {
+        // " Tauri," is 7 tokens, " Kubernetes," 12 and " Rust," 6
+        let vocabulary = words(&["Tauri", "Kubernetes", "Rust"]);
+        assert_eq!(build_prompt(&vocabulary, 14, count_chars), "Tauri, Rust.");
+        assert_eq!(
+            build_prompt(&vocabulary, 25, count_chars),
+            "Tauri, Kubernetes, Rust."
+        );
+    }
+
+    #[test]
+    fn empty_when_nothing_fits() {
+        let vocabulary = words(&["Kubernetes"]);
+        assert_eq!(build_prompt(&vocabulary, 5, count_chars), "");
+        assert_eq!(build_prompt(&[], 64, count_chars), "");
+    }
+}
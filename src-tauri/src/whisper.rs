@@ -1,115 +1,215 @@
 // Native Whisper support - only compiled when feature is enabled
 // Falls back to WASM Whisper in the frontend when disabled
 
+mod catalog;
+mod options;
+#[cfg(feature = "native-whisper")]
+mod download;
+#[cfg(feature = "native-whisper")]
+mod integrity;
+#[cfg(feature = "native-whisper")]
+mod jobs;
+#[cfg(feature = "native-whisper")]
+mod stream;
+#[cfg(feature = "native-whisper")]
+mod worker;
+
 #[cfg(feature = "native-whisper")]
 mod native {
-    use std::path::PathBuf;
-    use std::sync::Mutex;
+    use super::catalog::{self, get_models_dir};
+    use super::download;
+    use super::integrity::{self, GgmlHeader};
+    use super::options::{TranscribeOptions, MAX_PROMPT_TOKENS};
+    use super::worker::{self, Priority};
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, Mutex, RwLock};
     use tauri::{command, AppHandle, Emitter, Runtime};
-    use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+    use whisper_rs::{
+        FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters, WhisperState,
+        WhisperToken,
+    };
 
-    // Global state for loaded model
-    static WHISPER_CTX: Mutex<Option<WhisperContext>> = Mutex::new(None);
+    // Global state for loaded model. Inference holds its own reference, so
+    // checking or swapping the model never waits for a transcription.
+    static WHISPER_CTX: RwLock<Option<Arc<WhisperContext>>> = RwLock::new(None);
     static MODEL_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
 
-    /// Get the models directory for storing Whisper models
-    fn get_models_dir() -> Result<PathBuf, String> {
-        let data_dir = dirs::data_local_dir()
-            .ok_or_else(|| "Could not find local data directory".to_string())?;
-        let models_dir = data_dir.join("voice-dictation-widget").join("models");
-        std::fs::create_dir_all(&models_dir)
-            .map_err(|e| format!("Failed to create models directory: {}", e))?;
-        Ok(models_dir)
+    /// The currently loaded model, if any
+    pub(super) fn loaded_model() -> Option<Arc<WhisperContext>> {
+        WHISPER_CTX.read().ok().and_then(|ctx| ctx.clone())
     }
 
     /// Check if the Whisper model is already downloaded
     #[command]
-    pub fn is_whisper_model_downloaded() -> bool {
-        if let Ok(models_dir) = get_models_dir() {
-            let model_path = models_dir.join("ggml-base.en.bin");
-            model_path.exists()
-        } else {
-            false
-        }
+    pub fn is_whisper_model_downloaded(model_id: Option<String>) -> bool {
+        let Ok(model) = catalog::resolve(model_id.as_deref()) else {
+            return false;
+        };
+        catalog::find_model_file(&model)
+            .map(|path| GgmlHeader::read(&path).is_ok())
+            .unwrap_or(false)
     }
 
     /// Get the path to the downloaded model
     #[command]
-    pub fn get_whisper_model_path() -> Result<String, String> {
-        let models_dir = get_models_dir()?;
-        let model_path = models_dir.join("ggml-base.en.bin");
-        if model_path.exists() {
-            Ok(model_path.to_string_lossy().to_string())
-        } else {
-            Err("Model not downloaded".to_string())
+    pub fn get_whisper_model_path(model_id: Option<String>) -> Result<String, String> {
+        let model = catalog::resolve(model_id.as_deref())?;
+        match catalog::find_model_file(&model) {
+            Some(model_path) => Ok(model_path.to_string_lossy().to_string()),
+            None => Err(format!("Model '{}' not downloaded", model.id)),
         }
     }
 
     /// Download the Whisper model with progress updates
     #[command]
-    pub async fn download_whisper_model<R: Runtime>(app: AppHandle<R>) -> Result<String, String> {
-        let models_dir = get_models_dir()?;
-        let model_path = models_dir.join("ggml-base.en.bin");
+    pub async fn download_whisper_model<R: Runtime>(
+        app: AppHandle<R>,
+        model_id: Option<String>,
+    ) -> Result<String, String> {
+        let model = catalog::resolve(model_id.as_deref())?;
         
-        // Check if already downloaded
-        if model_path.exists() {
-            return Ok(model_path.to_string_lossy().to_string());
+        // Check if already downloaded, locally or in the shared directory
+        if let Some(existing) = catalog::find_model_file(&model) {
+            return Ok(existing.to_string_lossy().to_string());
         }
         
-        // Download from Hugging Face
-        let url = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-base.en.bin";
+        if model.url.is_empty() {
+            return Err(format!("Model '{}' has no download URL; import it instead", model.id));
+        }
+        
+        let model_path = get_models_dir()?.join(&model.file_name);
+        
+        let _ = app.emit("whisper-download-start", &model.id);
+        
+        // Prefer the built-in or pinned hash; only models outside the
+        // published set need to ask Hugging Face for one
+        let expected_sha256 = match &model.sha256 {
+            Some(hash) => Some(hash.clone()),
+            None => download::fetch_published_sha256(&model.url).await,
+        };
         
-        let _ = app.emit("whisper-download-start", ());
+        let published = model.sha256.is_none() && expected_sha256.is_some();
+        let sha256 = download::download_model(&app, &model, &model_path, expected_sha256).await?;
         
-        let client = reqwest::Client::new();
-        let response = client.get(url)
-            .send()
-            .await
-            .map_err(|e| format!("Failed to start download: {}", e))?;
+        // Pin a hash the download was checked against so later verification
+        // works offline. A hash we only computed ourselves proves nothing.
+        if published {
+            let _ = catalog::pin_sha256(&model.id, &sha256);
+        }
         
-        let total_size = response.content_length().unwrap_or(0);
-        let downloaded: u64;
+        let _ = app.emit("whisper-download-complete", &model.id);
         
-        let mut file = std::fs::File::create(&model_path)
-            .map_err(|e| format!("Failed to create model file: {}", e))?;
+        Ok(model_path.to_string_lossy().to_string())
+    }
+
+    /// Import a model file from disk, for machines without internet access.
+    /// The file is hardlinked (or copied) into the models directory under the
+    /// name of the matching catalog entry. Unknown files are registered as
+    /// local models named after the file.
+    #[command]
+    pub async fn import_whisper_model(path: String, model_id: Option<String>) -> Result<String, String> {
+        let source = PathBuf::from(path);
+        let header = GgmlHeader::read(&source)?;
         
-        use std::io::Write;
-        let bytes = response.bytes().await
-            .map_err(|e| format!("Failed to download: {}", e))?;
+        let file_name = source
+            .file_name()
+            .and_then(|n| n.to_str())
+            .ok_or("Invalid model file name")?
+            .to_string();
         
-        file.write_all(&bytes)
-            .map_err(|e| format!("Failed to write model file: {}", e))?;
+        // Unknown files only get a manifest entry once they are in place
+        let mut unregistered = false;
+        let model = match model_id {
+            Some(id) => catalog::resolve(Some(&id))?,
+            None => match catalog::load_manifest()?.models.into_iter().find(|m| m.file_name == file_name) {
+                Some(model) => model,
+                None => {
+                    let id = file_name
+                        .trim_start_matches("ggml-")
+                        .trim_end_matches(".bin")
+                        .to_string();
+                    let model = catalog::local_model(&id, &file_name, header.is_multilingual());
+                    catalog::check_file_name(&model)?;
+                    unregistered = true;
+                    model
+                }
+            },
+        };
         
-        downloaded = bytes.len() as u64;
+        if model.multilingual != header.is_multilingual() {
+            return Err(format!(
+                "{} does not match model '{}' (multilingual: {})",
+                source.display(),
+                model.id,
+                model.multilingual
+            ));
+        }
         
-        let _ = app.emit("whisper-download-progress", serde_json::json!({
-            "downloaded": downloaded,
-            "total": total_size,
-            "progress": if total_size > 0 { (downloaded as f64 / total_size as f64 * 100.0) as u32 } else { 100 }
-        }));
+        let dest = get_models_dir()?.join(&model.file_name);
+        if dest.exists() {
+            return Err(format!("Model '{}' is already installed", model.id));
+        }
         
-        let _ = app.emit("whisper-download-complete", ());
+        let installed = dest.clone();
+        let expected_sha256 = model.sha256.clone();
+        let path = tauri::async_runtime::spawn_blocking(move || {
+            let sha256 = integrity::sha256_file(&source)?;
+            if let Some(expected) = &expected_sha256 {
+                if !sha256.eq_ignore_ascii_case(expected) {
+                    return Err(format!(
+                        "Checksum mismatch for {}: expected {}, got {}",
+                        source.display(),
+                        expected,
+                        sha256
+                    ));
+                }
+            }
+            
+            // Hardlink when source and models directory share a filesystem,
+            // otherwise copy through a .part file so a partial copy is never visible
+            if std::fs::hard_link(&source, &dest).is_err() {
+                let part = download::part_path(&dest);
+                std::fs::copy(&source, &part)
+                    .map_err(|e| format!("Failed to copy model file: {}", e))?;
+                std::fs::rename(&part, &dest)
+                    .map_err(|e| format!("Failed to move model into place: {}", e))?;
+            }
+            
+            Ok(dest.to_string_lossy().to_string())
+        })
+        .await
+        .map_err(|e| format!("Import task failed: {}", e))??;
         
-        Ok(model_path.to_string_lossy().to_string())
+        if unregistered {
+            if let Err(e) = catalog::register(model) {
+                let _ = std::fs::remove_file(&installed);
+                return Err(e);
+            }
+        }
+        
+        Ok(path)
     }
 
     /// Load the Whisper model into memory
     #[command]
-    pub fn load_whisper_model() -> Result<(), String> {
-        let models_dir = get_models_dir()?;
-        let model_path = models_dir.join("ggml-base.en.bin");
+    pub fn load_whisper_model(model_id: Option<String>) -> Result<(), String> {
+        let model = catalog::resolve(model_id.as_deref())?;
+        let model_path = catalog::find_model_file(&model).ok_or_else(|| {
+            format!(
+                "Model '{}' not downloaded. Call download_whisper_model first.",
+                model.id
+            )
+        })?;
         
-        if !model_path.exists() {
-            return Err("Model not downloaded. Call download_whisper_model first.".to_string());
-        }
+        integrity::check_header(&model_path)?;
         
         let ctx = WhisperContext::new_with_params(
             model_path.to_str().ok_or("Invalid model path")?,
             WhisperContextParameters::default()
         ).map_err(|e| format!("Failed to load Whisper model: {}", e))?;
         
-        let mut whisper_ctx = WHISPER_CTX.lock().map_err(|_| "Lock poisoned")?;
-        *whisper_ctx = Some(ctx);
+        let mut whisper_ctx = WHISPER_CTX.write().map_err(|_| "Lock poisoned")?;
+        *whisper_ctx = Some(Arc::new(ctx));
         
         let mut stored_path = MODEL_PATH.lock().map_err(|_| "Lock poisoned")?;
         *stored_path = Some(model_path);
@@ -117,107 +217,628 @@ mod native {
         Ok(())
     }
 
-    /// Check if Whisper model is loaded
+    /// Whether a model's hash could be checked against a reference
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub enum VerificationStatus {
+        /// The hash matches the catalog or the one published with the download
+        Verified,
+        /// The header is valid but there is no reference hash to compare with
+        Unverified,
+    }
+
+    #[derive(serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ModelVerification {
+        pub model_id: String,
+        pub path: String,
+        pub status: VerificationStatus,
+        pub sha256: String,
+        pub expected_sha256: Option<String>,
+        pub header: GgmlHeader,
+    }
+
+    /// Re-hash a downloaded model and check it against the manifest.
+    /// Corrupt files are quarantined and reported as an error; files with
+    /// no known hash are reported as unverified.
     #[command]
-    pub fn is_whisper_loaded() -> bool {
-        WHISPER_CTX.lock().map(|ctx| ctx.is_some()).unwrap_or(false)
+    pub async fn verify_whisper_model(model_id: Option<String>) -> Result<ModelVerification, String> {
+        let model = catalog::resolve(model_id.as_deref())?;
+        let model_path = catalog::find_model_file(&model)
+            .ok_or_else(|| format!("Model '{}' not downloaded", model.id))?;
+        
+        tauri::async_runtime::spawn_blocking(move || {
+            let sha256 = integrity::verify_file(&model_path, model.sha256.as_deref())?;
+            let header = GgmlHeader::read(&model_path)?;
+            let status = match model.sha256 {
+                Some(_) => VerificationStatus::Verified,
+                None => VerificationStatus::Unverified,
+            };
+            Ok(ModelVerification {
+                model_id: model.id,
+                path: model_path.to_string_lossy().to_string(),
+                status,
+                sha256,
+                expected_sha256: model.sha256,
+                header,
+            })
+        })
+        .await
+        .map_err(|e| format!("Verification task failed: {}", e))?
     }
 
-    /// Transcribe audio data using native Whisper
-    /// Expects raw PCM audio data at 16kHz mono
+    #[derive(serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct InstalledModel {
+        /// Catalog id, `None` for files not listed in the manifest
+        pub id: Option<String>,
+        pub file_name: String,
+        pub path: String,
+        pub size_on_disk: u64,
+        /// Model size class (`tiny`, `base`, ...) read from the header
+        pub model_type: Option<String>,
+        pub quantization: Option<String>,
+        pub multilingual: Option<bool>,
+        /// True for models in the shared read-only directory
+        pub shared: bool,
+        pub loaded: bool,
+        /// Why the header could not be read, if it could not
+        pub error: Option<String>,
+    }
+
+    fn scan_models_dir(
+        dir: &Path,
+        shared: bool,
+        catalog: &[catalog::ModelSpec],
+        loaded_path: Option<&Path>,
+        models: &mut Vec<InstalledModel>,
+    ) {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Some(file_name) = path.file_name().and_then(|n| n.to_str()).map(str::to_string) else {
+                continue;
+            };
+            if !path.is_file() || !file_name.ends_with(".bin") {
+                continue;
+            }
+            // A local copy shadows the shared one with the same name
+            if models.iter().any(|m| m.file_name == file_name) {
+                continue;
+            }
+            
+            let header = GgmlHeader::read(&path);
+            models.push(InstalledModel {
+                id: catalog.iter().find(|m| m.file_name == file_name).map(|m| m.id.clone()),
+                size_on_disk: entry.metadata().map(|m| m.len()).unwrap_or(0),
+                model_type: header.as_ref().ok().map(|h| h.model_type().to_string()),
+                quantization: header.as_ref().ok().map(|h| h.quantization().to_string()),
+                multilingual: header.as_ref().ok().map(|h| h.is_multilingual()),
+                shared,
+                loaded: loaded_path == Some(path.as_path()),
+                error: header.err(),
+                path: path.to_string_lossy().to_string(),
+                file_name,
+            });
+        }
+    }
+
+    /// List the models installed locally and in the shared directory
+    #[command]
+    pub fn list_whisper_models() -> Result<Vec<InstalledModel>, String> {
+        let catalog = catalog::load_manifest()?.models;
+        let loaded_path = MODEL_PATH.lock().map_err(|_| "Lock poisoned")?.clone();
+        
+        let mut models = Vec::new();
+        scan_models_dir(&get_models_dir()?, false, &catalog, loaded_path.as_deref(), &mut models);
+        if let Some(shared_dir) = catalog::shared_models_dir() {
+            scan_models_dir(&shared_dir, true, &catalog, loaded_path.as_deref(), &mut models);
+        }
+        
+        Ok(models)
+    }
+
+    /// Delete a downloaded model, unloading it first if it is in use
     #[command]
-    pub fn transcribe_audio_native(audio_data: Vec<f32>) -> Result<String, String> {
-        let whisper_ctx = WHISPER_CTX.lock().map_err(|_| "Lock poisoned")?;
-        let ctx = whisper_ctx.as_ref()
-            .ok_or("Whisper model not loaded. Call load_whisper_model first.")?;
+    pub fn delete_whisper_model(model_id: String) -> Result<(), String> {
+        let model = catalog::resolve(Some(&model_id))?;
+        let model_path = get_models_dir()?.join(&model.file_name);
+        let part = download::part_path(&model_path);
         
+        if !model_path.exists() && !part.exists() {
+            return match catalog::find_model_file(&model) {
+                Some(path) => Err(format!("{} is in the shared models directory and cannot be deleted", path.display())),
+                None => Err(format!("Model '{}' not downloaded", model.id)),
+            };
+        }
+        
+        let is_loaded = MODEL_PATH
+            .lock()
+            .map_err(|_| "Lock poisoned")?
+            .as_deref()
+            == Some(model_path.as_path());
+        if is_loaded {
+            unload_whisper_model()?;
+        }
+        
+        for path in [&model_path, &part] {
+            if path.exists() {
+                std::fs::remove_file(path)
+                    .map_err(|e| format!("Failed to delete {}: {}", path.display(), e))?;
+            }
+        }
+        
+        Ok(())
+    }
+
+    /// Check if Whisper model is loaded
+    #[command]
+    pub fn is_whisper_loaded() -> bool {
+        WHISPER_CTX.read().map(|ctx| ctx.is_some()).unwrap_or(false)
+    }
+
+    /// A word assembled from Whisper tokens
+    #[derive(Debug, Clone, serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Word {
+        pub start_ms: i64,
+        pub end_ms: i64,
+        pub text: String,
+        /// Lowest token probability within the word
+        pub probability: f32,
+    }
+
+    /// A transcribed span of audio
+    #[derive(Debug, Clone, serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Segment {
+        pub start_ms: i64,
+        pub end_ms: i64,
+        pub text: String,
+        /// Mean log probability of the segment's text tokens
+        pub avg_logprob: f32,
+        /// Probability that the window the segment starts in holds no
+        /// speech. Only filled in when requested.
+        pub no_speech_prob: Option<f32>,
+        /// Only filled in when word timestamps are requested
+        pub words: Option<Vec<Word>>,
+    }
+
+    #[derive(Debug, Clone, serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct TranscriptionResult {
+        pub text: String,
+        pub segments: Vec<Segment>,
+        /// Language the audio was decoded as
+        pub language: Option<String>,
+        /// Detection confidence, only set when the language was auto-detected
+        pub language_probability: Option<f32>,
+    }
+
+    /// Threads used when the options do not set a count, matching whisper.cpp's default
+    fn default_threads() -> usize {
+        std::thread::available_parallelism()
+            .map(|n| n.get().min(4))
+            .unwrap_or(1)
+    }
+
+    /// Options passed to a command, or the saved defaults when there are none
+    pub(super) fn resolve_options(
+        options: Option<TranscribeOptions>,
+    ) -> Result<TranscribeOptions, String> {
+        let options = match options {
+            Some(options) => options,
+            None => crate::settings::load()?.transcribe,
+        };
+        options.validate()?;
+        Ok(options)
+    }
+
+    /// Callbacks whisper.cpp invokes while decoding, and optional extras
+    #[derive(Default)]
+    pub(super) struct DecodeHooks {
+        /// Progress of this decode in percent
+        pub progress: Option<Box<dyn FnMut(i32)>>,
+        /// Return true to stop decoding as soon as possible
+        pub abort: Option<Box<dyn FnMut() -> bool>>,
+        /// Fill in `Segment::no_speech_prob`, at the cost of another encoder
+        /// pass per 30 second window
+        pub no_speech_prob: bool,
+    }
+
+    /// Mel frames in one Whisper window, 10 ms each
+    const WINDOW_FRAMES: i64 = 3000;
+
+    /// Probability that the window starting `offset` frames into the audio
+    /// in `state` holds no speech. whisper.cpp works this out from the first
+    /// decoder step of each window but only exposes it for a context's
+    /// built-in state, so the step is repeated here with the same prompt.
+    fn no_speech_prob(
+        ctx: &WhisperContext,
+        state: &mut WhisperState,
+        offset: i64,
+        prompt: &[WhisperToken],
+        threads: usize,
+    ) -> Result<f32, String> {
+        state
+            .encode(offset.max(0) as usize, threads)
+            .map_err(|e| format!("Failed to encode audio: {}", e))?;
+
+        // Only the first token's logits can be read back, so the last token
+        // is decoded on its own
+        let (last, prefix) = prompt.split_last().ok_or("Empty decoder prompt")?;
+        if !prefix.is_empty() {
+            state
+                .decode(prefix, 0, threads)
+                .map_err(|e| format!("Failed to decode prompt: {}", e))?;
+        }
+        state
+            .decode(&[*last], prefix.len(), threads)
+            .map_err(|e| format!("Failed to decode prompt: {}", e))?;
+
+        let logits = state
+            .get_logits()
+            .map_err(|e| format!("Failed to read logits: {}", e))?;
+        let max = logits.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        let sum: f32 = logits.iter().map(|l| (l - max).exp()).sum();
+        let nosp = logits
+            .get(ctx.token_nosp() as usize)
+            .ok_or("No-speech token out of range")?;
+        Ok((nosp - max).exp() / sum)
+    }
+
+    /// Run Whisper over 16kHz mono audio and collect the decoded segments.
+    /// Only called on the inference worker.
+    pub(super) fn transcribe(
+        ctx: &WhisperContext,
+        audio_data: &[f32],
+        options: &TranscribeOptions,
+        initial_prompt: Option<&str>,
+    ) -> Result<TranscriptionResult, String> {
+        transcribe_with_hooks(ctx, audio_data, options, initial_prompt, DecodeHooks::default())
+    }
+
+    /// Same as `transcribe`, reporting progress and checking for cancellation
+    pub(super) fn transcribe_with_hooks(
+        ctx: &WhisperContext,
+        audio_data: &[f32],
+        options: &TranscribeOptions,
+        initial_prompt: Option<&str>,
+        hooks: DecodeHooks,
+    ) -> Result<TranscriptionResult, String> {
         let mut state = ctx.create_state()
             .map_err(|e| format!("Failed to create Whisper state: {}", e))?;
         
-        let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+        let strategy = if options.beam_size > 1 {
+            SamplingStrategy::BeamSearch {
+                beam_size: options.beam_size as i32,
+                patience: -1.0,
+            }
+        } else {
+            SamplingStrategy::Greedy {
+                best_of: options.best_of as i32,
+            }
+        };
+        let mut params = FullParams::new(strategy);
+        let threads = options.threads.map(|n| n as usize).unwrap_or_else(default_threads);
+        
+        // English-only models can neither detect nor transcribe other languages
+        let (language, language_probability) = if !ctx.is_multilingual() {
+            if !options.is_auto_language() && options.language != "en" {
+                return Err(format!(
+                    "The loaded model is English-only and cannot transcribe '{}'. Load a multilingual model instead.",
+                    options.language
+                ));
+            }
+            ("en", None)
+        } else if options.is_auto_language() {
+            state.pcm_to_mel(audio_data, threads)
+                .map_err(|e| format!("Failed to compute spectrogram: {}", e))?;
+            let (lang_id, probs) = state.lang_detect(0, threads)
+                .map_err(|e| format!("Language detection failed: {}", e))?;
+            let language = whisper_rs::get_lang_str(lang_id)
+                .ok_or_else(|| format!("Unknown language id {}", lang_id))?;
+            (language, probs.get(lang_id as usize).copied())
+        } else {
+            if whisper_rs::get_lang_id(&options.language).is_none() {
+                return Err(format!("Unsupported language '{}'", options.language));
+            }
+            (options.language.as_str(), None)
+        };
         
-        // Configure for English
-        params.set_language(Some("en"));
+        params.set_language(Some(language));
+        params.set_translate(options.translate && ctx.is_multilingual());
         params.set_print_special(false);
         params.set_print_progress(false);
         params.set_print_realtime(false);
         params.set_print_timestamps(false);
-        params.set_suppress_blank(true);
         params.set_single_segment(false);
+        params.set_n_threads(threads as i32);
+        params.set_temperature(options.temperature);
+        params.set_temperature_inc(options.temperature_inc);
+        params.set_no_speech_thold(options.no_speech_threshold);
+        params.set_suppress_blank(options.suppress_blank);
+        params.set_suppress_nst(options.suppress_non_speech_tokens);
+        
+        // whisper.cpp only honours max_len with token timestamps enabled
+        params.set_token_timestamps(options.word_timestamps || options.max_segment_len > 0);
+        if options.max_segment_len > 0 {
+            params.set_max_len(options.max_segment_len as i32);
+            params.set_split_on_word(true);
+        }
+        
+        // User vocabulary first, then the configured prompt, then any caller
+        // context such as the previous window, which Whisper weighs most
+        let vocabulary = crate::vocabulary::load().unwrap_or_default();
+        let vocabulary_prompt = crate::vocabulary::build_prompt(
+            &vocabulary,
+            options.vocabulary_token_budget as usize,
+            |text| {
+                ctx.tokenize(text, MAX_PROMPT_TOKENS as usize)
+                    .map(|tokens| tokens.len())
+                    .unwrap_or(usize::MAX)
+            },
+        );
+        let prompt = [
+            Some(vocabulary_prompt.as_str()),
+            options.initial_prompt.as_deref(),
+            initial_prompt,
+        ]
+            .into_iter()
+            .flatten()
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        if !prompt.is_empty() {
+            params.set_initial_prompt(&prompt);
+        }
+        
+        if let Some(progress) = hooks.progress {
+            params.set_progress_callback_safe(progress);
+        }
+        if let Some(abort) = hooks.abort {
+            params.set_abort_callback_safe(abort);
+        }
         
         // Run inference
-        state.full(params, &audio_data)
+        state.full(params, audio_data)
             .map_err(|e| format!("Transcription failed: {}", e))?;
         
         // Collect all segments
         let num_segments = state.full_n_segments()
             .map_err(|e| format!("Failed to get segment count: {}", e))?;
+
+        // The prompt whisper.cpp starts each window with: earlier text, then
+        // start of transcript, language and task
+        let mut decoder_prompt = Vec::new();
+        if hooks.no_speech_prob {
+            if !prompt.is_empty() {
+                let tokens = ctx.tokenize(&prompt, MAX_PROMPT_TOKENS as usize)
+                    .map_err(|e| format!("Failed to tokenize prompt: {}", e))?;
+                let keep = tokens.len().min(ctx.n_text_ctx() as usize / 2);
+                decoder_prompt.push(ctx.token_prev());
+                decoder_prompt.extend_from_slice(&tokens[tokens.len() - keep..]);
+            }
+            decoder_prompt.push(ctx.token_sot());
+            if ctx.is_multilingual() {
+                let lang_id = whisper_rs::get_lang_id(language)
+                    .ok_or_else(|| format!("Unsupported language '{}'", language))?;
+                decoder_prompt.push(ctx.token_lang(lang_id));
+                decoder_prompt.push(if options.translate {
+                    ctx.token_translate()
+                } else {
+                    ctx.token_transcribe()
+                });
+            }
+        }
+        let mut window: Option<(i64, Option<f32>)> = None;
         
-        let mut result = String::new();
+        // Token ids from end-of-text upwards are special (timestamps, language, ...)
+        let token_eot = ctx.token_eot();
+        let word_timestamps = options.word_timestamps;
+        
+        let mut segments = Vec::with_capacity(num_segments.max(0) as usize);
         for i in 0..num_segments {
-            if let Ok(segment) = state.full_get_segment_text(i) {
-                result.push_str(&segment);
-                result.push(' ');
+            let Ok(text) = state.full_get_segment_text(i) else {
+                continue;
+            };
+            
+            let num_tokens = state.full_n_tokens(i).unwrap_or(0);
+            let mut logprob_sum = 0.0;
+            let mut text_tokens = 0;
+            let mut words: Vec<Word> = Vec::new();
+            
+            for t in 0..num_tokens {
+                let Ok(data) = state.full_get_token_data(i, t) else {
+                    continue;
+                };
+                if data.id >= token_eot {
+                    continue;
+                }
+                logprob_sum += data.plog;
+                text_tokens += 1;
+                
+                if !word_timestamps {
+                    continue;
+                }
+                let Ok(token_text) = state.full_get_token_text(i, t) else {
+                    continue;
+                };
+                // A leading space marks the start of a new word
+                match words.last_mut() {
+                    Some(word) if !token_text.starts_with(' ') => {
+                        word.text.push_str(&token_text);
+                        word.end_ms = data.t1 * 10;
+                        word.probability = word.probability.min(data.p);
+                    }
+                    _ => words.push(Word {
+                        start_ms: data.t0 * 10,
+                        end_ms: data.t1 * 10,
+                        text: token_text,
+                        probability: data.p,
+                    }),
+                }
+            }
+            
+            for word in &mut words {
+                word.text = word.text.trim().to_string();
             }
+            words.retain(|w| !w.text.is_empty());
+            
+            // Whisper timestamps are in centiseconds, one mel frame each
+            let t0 = state.full_get_segment_t0(i).unwrap_or(0);
+            let t1 = state.full_get_segment_t1(i).unwrap_or(0);
+
+            // Segments share the value of the window they start in. A failure
+            // here leaves the value out rather than losing the transcript.
+            let no_speech = if hooks.no_speech_prob {
+                match window {
+                    Some((start, prob)) if t0 < start + WINDOW_FRAMES => prob,
+                    _ => {
+                        let start = if window.is_none() { 0 } else { t0 };
+                        let prob =
+                            no_speech_prob(ctx, &mut state, start, &decoder_prompt, threads).ok();
+                        window = Some((start, prob));
+                        prob
+                    }
+                }
+            } else {
+                None
+            };
+
+            segments.push(Segment {
+                start_ms: t0 * 10,
+                end_ms: t1 * 10,
+                text,
+                avg_logprob: if text_tokens > 0 { logprob_sum / text_tokens as f32 } else { 0.0 },
+                no_speech_prob: no_speech,
+                words: word_timestamps.then_some(words),
+            });
         }
         
-        Ok(result.trim().to_string())
+        Ok(TranscriptionResult {
+            text: join_segments(&segments),
+            segments,
+            language: Some(language.to_string()),
+            language_probability,
+        })
     }
 
-    /// Transcribe audio from a WAV file path
-    #[command]
-    pub fn transcribe_audio_file(file_path: String) -> Result<String, String> {
-        // Read WAV file
-        let mut reader = hound::WavReader::open(&file_path)
-            .map_err(|e| format!("Failed to open audio file: {}", e))?;
-        
-        let spec = reader.spec();
-        
-        // Convert to f32 samples
-        let samples: Vec<f32> = if spec.bits_per_sample == 16 {
-            reader.samples::<i16>()
-                .filter_map(|s| s.ok())
-                .map(|s| s as f32 / 32768.0)
-                .collect()
-        } else {
-            reader.samples::<i32>()
-                .filter_map(|s| s.ok())
-                .map(|s| s as f32 / 2147483648.0)
-                .collect()
-        };
-        
-        // Resample to 16kHz if needed
-        let samples = if spec.sample_rate != 16000 {
-            let ratio = 16000.0 / spec.sample_rate as f32;
-            let new_len = (samples.len() as f32 * ratio) as usize;
-            let mut resampled = Vec::with_capacity(new_len);
-            for i in 0..new_len {
-                let src_idx = (i as f32 / ratio) as usize;
-                if src_idx < samples.len() {
-                    resampled.push(samples[src_idx]);
-                }
+    /// Join segment texts into a single transcript
+    pub(super) fn join_segments(segments: &[Segment]) -> String {
+        let mut result = String::new();
+        for segment in segments {
+            result.push_str(&segment.text);
+            result.push(' ');
+        }
+        result.trim().to_string()
+    }
+
+    /// Shift segment and word timestamps by `offset_ms`
+    pub(super) fn offset_segment(mut segment: Segment, offset_ms: i64) -> Segment {
+        segment.start_ms += offset_ms;
+        segment.end_ms += offset_ms;
+        if let Some(words) = &mut segment.words {
+            for word in words {
+                word.start_ms += offset_ms;
+                word.end_ms += offset_ms;
             }
-            resampled
-        } else {
-            samples
-        };
-        
-        // Convert stereo to mono if needed
-        let samples = if spec.channels == 2 {
-            samples.chunks(2)
-                .map(|chunk| (chunk[0] + chunk.get(1).unwrap_or(&0.0)) / 2.0)
-                .collect()
-        } else {
-            samples
-        };
-        
-        transcribe_audio_native(samples)
+        }
+        segment
+    }
+
+    /// Queue audio on the inference worker and wait for the result
+    /// without tying up an async runtime thread
+    async fn transcribe_queued(
+        priority: Priority,
+        audio_data: Vec<f32>,
+        options: TranscribeOptions,
+        no_speech_prob: bool,
+    ) -> Result<TranscriptionResult, String> {
+        let vad = crate::settings::load()?.vad;
+        tauri::async_runtime::spawn_blocking(move || -> Result<TranscriptionResult, String> {
+            // Whisper tends to make up text for silence, so only the
+            // speech is decoded
+            let mut offset_ms = 0;
+            let mut audio_data = audio_data;
+            if vad.trim_silence {
+                let Some(speech) = crate::vad::trim_silence(&audio_data, &vad) else {
+                    return Ok(TranscriptionResult {
+                        text: String::new(),
+                        segments: Vec::new(),
+                        language: None,
+                        language_probability: None,
+                    });
+                };
+                offset_ms = (speech.start * 1000 / crate::audio::WHISPER_SAMPLE_RATE as usize) as i64;
+                audio_data = audio_data[speech].to_vec();
+            }
+
+            let mut result = worker::run_once(priority, move |ctx| {
+                let hooks = DecodeHooks {
+                    no_speech_prob,
+                    ..DecodeHooks::default()
+                };
+                transcribe_with_hooks(ctx, &audio_data, &options, None, hooks)
+            })?;
+            result.segments = result
+                .segments
+                .into_iter()
+                .map(|s| offset_segment(s, offset_ms))
+                .collect();
+            Ok(result)
+        })
+        .await
+        .map_err(|e| format!("Transcription task failed: {}", e))?
+    }
+
+    /// Transcribe audio data using native Whisper
+    /// Expects raw PCM audio data at 16kHz mono
+    #[command]
+    pub async fn transcribe_audio_native(
+        audio_data: Vec<f32>,
+        options: Option<TranscribeOptions>,
+    ) -> Result<String, String> {
+        let options = resolve_options(options)?;
+        Ok(transcribe_queued(Priority::Live, audio_data, options, false).await?.text)
+    }
+
+    /// Transcribe audio data and return segment timing, confidence, the
+    /// no-speech probability and the detected language, optionally with
+    /// word-level timestamps
+    #[command]
+    pub async fn transcribe_audio_detailed(
+        audio_data: Vec<f32>,
+        options: Option<TranscribeOptions>,
+    ) -> Result<TranscriptionResult, String> {
+        let options = resolve_options(options)?;
+        transcribe_queued(Priority::Live, audio_data, options, true).await
+    }
+
+    /// Transcribe an audio file (WAV, or FLAC/MP3/Ogg/M4A with the
+    /// `audio-formats` feature). Long files are better served by
+    /// `start_file_transcription`.
+    #[command]
+    pub async fn transcribe_audio_file(file_path: String) -> Result<String, String> {
+        let options = resolve_options(None)?;
+        let samples = tauri::async_runtime::spawn_blocking(move || {
+            crate::audio::decode_file(Path::new(&file_path))
+        })
+        .await
+        .map_err(|e| format!("Decoding task failed: {}", e))??;
+        Ok(transcribe_queued(Priority::Batch, samples, options, false).await?.text)
     }
 
     /// Unload the Whisper model from memory
     #[command]
     pub fn unload_whisper_model() -> Result<(), String> {
-        let mut whisper_ctx = WHISPER_CTX.lock().map_err(|_| "Lock poisoned")?;
+        // A transcription already running keeps its own reference and the
+        // model is freed once it finishes
+        let mut whisper_ctx = WHISPER_CTX.write().map_err(|_| "Lock poisoned")?;
         *whisper_ctx = None;
         
         let mut stored_path = MODEL_PATH.lock().map_err(|_| "Lock poisoned")?;
@@ -230,6 +851,27 @@ mod native {
 // Re-export native commands when feature is enabled
 #[cfg(feature = "native-whisper")]
 pub use native::*;
+#[cfg(feature = "native-whisper")]
+pub use jobs::*;
+#[cfg(feature = "native-whisper")]
+pub use stream::*;
+#[cfg(feature = "native-whisper")]
+pub use worker::*;
+
+pub use options::TranscribeOptions;
+
+/// List the models available for download, from the built-in catalog
+/// and the user-editable manifest
+#[tauri::command]
+pub fn list_whisper_catalog() -> Result<Vec<catalog::ModelSpec>, String> {
+    Ok(catalog::load_manifest()?.models)
+}
+
+/// Set the model used when commands are called without a model id
+#[tauri::command]
+pub fn set_default_whisper_model(model_id: String) -> Result<(), String> {
+    catalog::set_default_model(&model_id)
+}
 
 // Stub implementations when native whisper is disabled
 // These allow the app to compile and run, falling back to WASM Whisper
@@ -239,25 +881,49 @@ use tauri::command;
 
 #[cfg(not(feature = "native-whisper"))]
 #[command]
-pub fn is_whisper_model_downloaded() -> bool {
+pub fn is_whisper_model_downloaded(_model_id: Option<String>) -> bool {
     false
 }
 
 #[cfg(not(feature = "native-whisper"))]
 #[command]
-pub fn get_whisper_model_path() -> Result<String, String> {
+pub fn get_whisper_model_path(_model_id: Option<String>) -> Result<String, String> {
+    Err("Native Whisper not enabled. Using WASM fallback.".to_string())
+}
+
+#[cfg(not(feature = "native-whisper"))]
+#[command]
+pub async fn download_whisper_model(_model_id: Option<String>) -> Result<String, String> {
     Err("Native Whisper not enabled. Using WASM fallback.".to_string())
 }
 
 #[cfg(not(feature = "native-whisper"))]
 #[command]
-pub async fn download_whisper_model() -> Result<String, String> {
+pub async fn import_whisper_model(_path: String, _model_id: Option<String>) -> Result<String, String> {
     Err("Native Whisper not enabled. Using WASM fallback.".to_string())
 }
 
 #[cfg(not(feature = "native-whisper"))]
 #[command]
-pub fn load_whisper_model() -> Result<(), String> {
+pub fn load_whisper_model(_model_id: Option<String>) -> Result<(), String> {
+    Err("Native Whisper not enabled. Using WASM fallback.".to_string())
+}
+
+#[cfg(not(feature = "native-whisper"))]
+#[command]
+pub async fn verify_whisper_model(_model_id: Option<String>) -> Result<(), String> {
+    Err("Native Whisper not enabled. Using WASM fallback.".to_string())
+}
+
+#[cfg(not(feature = "native-whisper"))]
+#[command]
+pub fn list_whisper_models() -> Result<Vec<()>, String> {
+    Ok(Vec::new())
+}
+
+#[cfg(not(feature = "native-whisper"))]
+#[command]
+pub fn delete_whisper_model(_model_id: String) -> Result<(), String> {
     Err("Native Whisper not enabled. Using WASM fallback.".to_string())
 }
 
@@ -269,7 +935,19 @@ pub fn is_whisper_loaded() -> bool {
 
 #[cfg(not(feature = "native-whisper"))]
 #[command]
-pub fn transcribe_audio_native(_audio_data: Vec<f32>) -> Result<String, String> {
+pub fn transcribe_audio_native(
+    _audio_data: Vec<f32>,
+    _options: Option<TranscribeOptions>,
+) -> Result<String, String> {
+    Err("Native Whisper not enabled. Using WASM fallback.".to_string())
+}
+
+#[cfg(not(feature = "native-whisper"))]
+#[command]
+pub fn transcribe_audio_detailed(
+    _audio_data: Vec<f32>,
+    _options: Option<TranscribeOptions>,
+) -> Result<(), String> {
     Err("Native Whisper not enabled. Using WASM fallback.".to_string())
 }
 
@@ -284,3 +962,54 @@ pub fn transcribe_audio_file(_file_path: String) -> Result<String, String> {
 pub fn unload_whisper_model() -> Result<(), String> {
     Ok(())
 }
+
+#[cfg(not(feature = "native-whisper"))]
+#[command]
+pub fn start_stream(_options: Option<TranscribeOptions>) -> Result<u64, String> {
+    Err("Native Whisper not enabled. Using WASM fallback.".to_string())
+}
+
+#[cfg(not(feature = "native-whisper"))]
+#[command]
+pub fn push_audio(_session_id: u64, _chunk: Vec<f32>) -> Result<(), String> {
+    Err("Native Whisper not enabled. Using WASM fallback.".to_string())
+}
+
+#[cfg(not(feature = "native-whisper"))]
+#[command]
+pub async fn finish_stream(_session_id: u64) -> Result<String, String> {
+    Err("Native Whisper not enabled. Using WASM fallback.".to_string())
+}
+
+#[cfg(not(feature = "native-whisper"))]
+#[command]
+pub fn cancel_stream(_session_id: u64) -> Result<(), String> {
+    Err("Native Whisper not enabled. Using WASM fallback.".to_string())
+}
+
+#[cfg(not(feature = "native-whisper"))]
+#[command]
+pub fn start_file_transcription(
+    _file_path: String,
+    _options: Option<TranscribeOptions>,
+) -> Result<u64, String> {
+    Err("Native Whisper not enabled. Using WASM fallback.".to_string())
+}
+
+#[cfg(not(feature = "native-whisper"))]
+#[command]
+pub fn cancel_transcription(_job_id: u64) -> Result<(), String> {
+    Err("Native Whisper not enabled. Using WASM fallback.".to_string())
+}
+
+#[cfg(not(feature = "native-whisper"))]
+#[command]
+pub fn get_transcription_job(_job_id: u64) -> Result<(), String> {
+    Err("Native Whisper not enabled. Using WASM fallback.".to_string())
+}
+
+#[cfg(not(feature = "native-whisper"))]
+#[command]
+pub fn list_transcription_jobs() -> Result<Vec<()>, String> {
+    Ok(Vec::new())
+}
@@ -0,0 +1,358 @@
+// Whisper model catalog
+// Built-in list of the published ggml models, merged with a user-editable
+// `models.json` manifest stored next to the downloaded models.
+
+#![cfg_attr(not(feature = "native-whisper"), allow(dead_code))]
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+const MANIFEST_FILE: &str = "models.json";
+
+/// Overrides the `sharedModelsDir` setting, e.g. for managed deployments
+pub const SHARED_MODELS_DIR_ENV: &str = "VOICE_DICTATION_MODELS_DIR";
+const HF_BASE_URL: &str = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main";
+
+/// Model used when a command is called without an explicit model id
+pub const DEFAULT_MODEL_ID: &str = "base.en";
+
+const MB: u64 = 1024 * 1024;
+
+/// SHA-256 of the published files, the LFS object ids Hugging Face lists
+/// for the repository. Regenerate with `node scripts/model-hashes.mjs`.
+const PUBLISHED_SHA256: &[(&str, &str)] = &[];
+
+/// A single downloadable Whisper model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelSpec {
+    /// Identifier passed to the model commands, e.g. `base.en` or `small-q5_1`
+    pub id: String,
+    /// File name inside the models directory
+    pub file_name: String,
+    pub url: String,
+    /// Approximate download size in bytes
+    pub size: u64,
+    /// Expected SHA-256 of the file, hex encoded
+    #[serde(default)]
+    pub sha256: Option<String>,
+    /// False for the English-only `.en` models
+    pub multilingual: bool,
+    /// Quantization suffix (`q5_0`, `q5_1`, `q8_0`), `None` for f16 weights
+    #[serde(default)]
+    pub quantization: Option<String>,
+}
+
+/// On-disk manifest format
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Manifest {
+    #[serde(default = "default_model_id")]
+    pub default_model: String,
+    #[serde(default)]
+    pub models: Vec<ModelSpec>,
+}
+
+fn default_model_id() -> String {
+    DEFAULT_MODEL_ID.to_string()
+}
+
+/// Get the models directory for storing Whisper models
+pub fn get_models_dir() -> Result<PathBuf, String> {
+    let models_dir = crate::settings::app_data_dir()?.join("models");
+    std::fs::create_dir_all(&models_dir)
+        .map_err(|e| format!("Failed to create models directory: {}", e))?;
+    Ok(models_dir)
+}
+
+/// Read-only models directory shared between users or machines, taken
+/// from the environment or the settings file
+pub fn shared_models_dir() -> Option<PathBuf> {
+    std::env::var_os(SHARED_MODELS_DIR_ENV)
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| crate::settings::load().ok()?.shared_models_dir)
+}
+
+/// Locate an installed model, checking the local models directory first
+/// and then the shared one
+pub fn find_model_file(model: &ModelSpec) -> Option<PathBuf> {
+    let local = get_models_dir().ok().map(|dir| dir.join(&model.file_name));
+    let shared = shared_models_dir().map(|dir| dir.join(&model.file_name));
+
+    local.into_iter().chain(shared).find(|path| path.is_file())
+}
+
+fn spec(id: &str, size_mb: u64) -> ModelSpec {
+    let file_name = format!("ggml-{}.bin", id);
+    let quantization = id
+        .rsplit_once('-')
+        .map(|(_, suffix)| suffix)
+        .filter(|suffix| suffix.starts_with('q'))
+        .map(str::to_string);
+
+    ModelSpec {
+        id: id.to_string(),
+        url: format!("{}/{}", HF_BASE_URL, file_name),
+        file_name,
+        size: size_mb * MB,
+        sha256: PUBLISHED_SHA256
+            .iter()
+            .find(|(model_id, _)| *model_id == id)
+            .map(|(_, sha256)| sha256.to_string()),
+        multilingual: !id.contains(".en"),
+        quantization,
+    }
+}
+
+/// Models published in the whisper.cpp Hugging Face repository
+pub fn builtin_models() -> Vec<ModelSpec> {
+    vec![
+        spec("tiny.en", 75),
+        spec("tiny", 75),
+        spec("tiny.en-q5_1", 31),
+        spec("tiny-q5_1", 31),
+        spec("tiny.en-q8_0", 42),
+        spec("tiny-q8_0", 42),
+        spec("base.en", 142),
+        spec("base", 142),
+        spec("base.en-q5_1", 57),
+        spec("base-q5_1", 57),
+        spec("base.en-q8_0", 78),
+        spec("base-q8_0", 78),
+        spec("small.en", 466),
+        spec("small", 466),
+        spec("small.en-q5_1", 181),
+        spec("small-q5_1", 181),
+        spec("small.en-q8_0", 252),
+        spec("small-q8_0", 252),
+        spec("medium.en", 1500),
+        spec("medium", 1500),
+        spec("medium.en-q5_0", 514),
+        spec("medium-q5_0", 514),
+        spec("medium.en-q8_0", 785),
+        spec("medium-q8_0", 785),
+        spec("large-v3", 2950),
+        spec("large-v3-q5_0", 1080),
+        spec("large-v3-turbo", 1550),
+        spec("large-v3-turbo-q5_0", 547),
+        spec("large-v3-turbo-q8_0", 834),
+    ]
+}
+
+fn manifest_path() -> Result<PathBuf, String> {
+    Ok(get_models_dir()?.join(MANIFEST_FILE))
+}
+
+/// Read the manifest file as written, creating it on first use. The file
+/// only holds the user's entries, so changes to the built-in catalog still
+/// reach models the user hasn't touched.
+fn read_user_manifest() -> Result<Manifest, String> {
+    let path = manifest_path()?;
+
+    if !path.exists() {
+        let manifest = Manifest {
+            default_model: default_model_id(),
+            models: Vec::new(),
+        };
+        save_manifest(&manifest)?;
+        return Ok(manifest);
+    }
+
+    let contents = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read model manifest: {}", e))?;
+    let mut manifest: Manifest = serde_json::from_str(&contents)
+        .map_err(|e| format!("Invalid model manifest {}: {}", path.display(), e))?;
+
+    // Older versions seeded the file with a copy of the built-in catalog
+    if drop_builtin_copies(&mut manifest.models, &builtin_models()) {
+        save_manifest(&manifest)?;
+    }
+    Ok(manifest)
+}
+
+/// Remove entries identical to a built-in one. Returns whether any were removed.
+fn drop_builtin_copies(models: &mut Vec<ModelSpec>, builtin: &[ModelSpec]) -> bool {
+    let before = models.len();
+    models.retain(|entry| !builtin.contains(entry));
+    models.len() != before
+}
+
+/// Load the effective catalog, the built-in models merged with the manifest
+pub fn load_manifest() -> Result<Manifest, String> {
+    let user = read_user_manifest()?;
+    Ok(Manifest {
+        default_model: user.default_model,
+        models: merge(builtin_models(), user.models),
+    })
+}
+
+/// Entries from `user` override entries with the same id in `models`, and
+/// additional entries are appended
+fn merge(mut models: Vec<ModelSpec>, user: Vec<ModelSpec>) -> Vec<ModelSpec> {
+    for entry in user {
+        match models.iter_mut().find(|m| m.id == entry.id) {
+            Some(existing) => *existing = entry,
+            None => models.push(entry),
+        }
+    }
+    models
+}
+
+pub fn save_manifest(manifest: &Manifest) -> Result<(), String> {
+    let path = manifest_path()?;
+    let contents = serde_json::to_string_pretty(manifest)
+        .map_err(|e| format!("Failed to serialize model manifest: {}", e))?;
+    std::fs::write(&path, contents)
+        .map_err(|e| format!("Failed to write model manifest: {}", e))
+}
+
+/// Look up a model by id, falling back to the manifest default
+pub fn resolve(model_id: Option<&str>) -> Result<ModelSpec, String> {
+    let manifest = load_manifest()?;
+    let id = model_id.unwrap_or(&manifest.default_model);
+
+    let model = manifest
+        .models
+        .into_iter()
+        .find(|m| m.id == id)
+        .ok_or_else(|| format!("Unknown Whisper model '{}'", id))?;
+
+    check_file_name(&model)?;
+    Ok(model)
+}
+
+/// The manifest is user-editable, so never let it point outside the models directory
+pub fn check_file_name(model: &ModelSpec) -> Result<(), String> {
+    if model.file_name.contains(['/', '\\']) || model.file_name.starts_with('.') {
+        return Err(format!("Invalid file name for model '{}': {}", model.id, model.file_name));
+    }
+    Ok(())
+}
+
+/// Change the model used when no id is given
+pub fn set_default_model(model_id: &str) -> Result<(), String> {
+    if !load_manifest()?.models.iter().any(|m| m.id == model_id) {
+        return Err(format!("Unknown Whisper model '{}'", model_id));
+    }
+
+    let mut manifest = read_user_manifest()?;
+    manifest.default_model = model_id.to_string();
+    save_manifest(&manifest)
+}
+
+/// Record the hash of a verified download so later checks can detect
+/// corruption or tampering without network access
+pub fn pin_sha256(model_id: &str, sha256: &str) -> Result<(), String> {
+    let mut manifest = read_user_manifest()?;
+
+    match manifest.models.iter_mut().find(|m| m.id == model_id) {
+        Some(entry) => entry.sha256 = Some(sha256.to_string()),
+        None => {
+            let mut entry = resolve(Some(model_id))?;
+            entry.sha256 = Some(sha256.to_string());
+            manifest.models.push(entry);
+        }
+    }
+
+    save_manifest(&manifest)
+}
+
+/// Entry for a model that only exists locally, e.g. an imported fine-tune
+/// that is not part of the published catalog
+pub fn local_model(id: &str, file_name: &str, multilingual: bool) -> ModelSpec {
+    ModelSpec {
+        id: id.to_string(),
+        file_name: file_name.to_string(),
+        url: String::new(),
+        size: 0,
+        sha256: None,
+        multilingual,
+        quantization: None,
+    }
+}
+
+/// Add `entry` to the manifest, replacing any entry with the same id
+pub fn register(entry: ModelSpec) -> Result<ModelSpec, String> {
+    check_file_name(&entry)?;
+    let id = entry.id.clone();
+
+    let mut manifest = read_user_manifest()?;
+    manifest.models.retain(|m| m.id != id);
+    manifest.models.push(entry);
+    save_manifest(&manifest)?;
+
+    resolve(Some(&id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model(id: &str) -> ModelSpec {
+        builtin_models().into_iter().find(|m| m.id == id).unwrap()
+    }
+
+    #[test]
+    fn parses_quantization_from_the_id() {
+        assert_eq!(model("base.en").quantization, None);
+        assert_eq!(model("small-q5_1").quantization.as_deref(), Some("q5_1"));
+        assert_eq!(
+            model("medium.en-q8_0").quantization.as_deref(),
+            Some("q8_0")
+        );
+        // The last dash in these ids is part of the name
+        assert_eq!(model("large-v3").quantization, None);
+        assert_eq!(model("large-v3-turbo").quantization, None);
+        assert_eq!(
+            model("large-v3-turbo-q5_0").quantization.as_deref(),
+            Some("q5_0")
+        );
+
+        assert!(!model("tiny.en-q5_1").multilingual);
+        assert!(model("tiny-q5_1").multilingual);
+        assert_eq!(model("tiny").file_name, "ggml-tiny.bin");
+    }
+
+    #[test]
+    fn drops_only_unchanged_builtin_copies() {
+        let builtin = builtin_models();
+        let mut edited = model("base.en");
+        edited.sha256 = Some("ab".repeat(32));
+        let custom = local_model("my-finetune", "ggml-my-finetune.bin", true);
+
+        let mut models = vec![
+            model("tiny"),
+            edited.clone(),
+            custom.clone(),
+            model("small"),
+        ];
+        assert!(drop_builtin_copies(&mut models, &builtin));
+        assert_eq!(models, [edited, custom]);
+        assert!(!drop_builtin_copies(&mut models, &builtin));
+    }
+
+    #[test]
+    fn manifest_entries_override_and_extend_the_builtin_list() {
+        let mut edited = model("base.en");
+        edited.url = "https://example.com/ggml-base.en.bin".to_string();
+        let custom = local_model("my-finetune", "ggml-my-finetune.bin", true);
+
+        let builtin = builtin_models();
+        let merged = merge(builtin.clone(), vec![edited.clone(), custom.clone()]);
+        assert_eq!(merged.len(), builtin.len() + 1);
+        let position = builtin.iter().position(|m| m.id == "base.en").unwrap();
+        assert_eq!(merged[position], edited);
+        assert_eq!(merged.last(), Some(&custom));
+    }
+
+    #[test]
+    fn published_hashes_belong_to_builtin_models() {
+        let builtin = builtin_models();
+        for (id, sha256) in PUBLISHED_SHA256 {
+            assert!(crate::whisper::integrity::is_sha256(sha256), "{}", id);
+            let model = builtin.iter().find(|m| m.id == *id).unwrap();
+            assert_eq!(model.sha256.as_deref(), Some(*sha256));
+        }
+    }
+}
@@ -0,0 +1,230 @@
+// Streaming model download
+// Writes into `<file>.part`, resumes interrupted downloads with an HTTP Range
+// request and only renames the file into place once it is complete.
+
+use super::catalog::ModelSpec;
+use super::integrity;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Runtime};
+use tokio::fs::{self, OpenOptions};
+use tokio::io::AsyncWriteExt;
+
+/// Minimum time between two `whisper-download-progress` events
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+// Models currently being downloaded, so two calls never write the same .part file
+static IN_PROGRESS: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+
+/// Removes the model from `IN_PROGRESS` when the download ends, however it ends
+struct DownloadGuard(String);
+
+impl DownloadGuard {
+    fn acquire(model_id: &str) -> Result<Self, String> {
+        let mut in_progress = IN_PROGRESS.lock().map_err(|_| "Lock poisoned")?;
+        if !in_progress.get_or_insert_with(HashSet::new).insert(model_id.to_string()) {
+            return Err(format!("Model '{}' is already being downloaded", model_id));
+        }
+        Ok(Self(model_id.to_string()))
+    }
+}
+
+impl Drop for DownloadGuard {
+    fn drop(&mut self) {
+        if let Ok(mut in_progress) = IN_PROGRESS.lock() {
+            if let Some(set) = in_progress.as_mut() {
+                set.remove(&self.0);
+            }
+        }
+    }
+}
+
+/// Path of the partial file for a model destination
+pub fn part_path(dest: &Path) -> PathBuf {
+    let mut name = dest.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    dest.with_file_name(name)
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DownloadProgress<'a> {
+    model_id: &'a str,
+    downloaded: u64,
+    total: u64,
+    progress: u32,
+    bytes_per_second: u64,
+    eta_seconds: Option<u64>,
+    resumed: bool,
+}
+
+/// SHA-256 published by Hugging Face for an LFS file, for models missing
+/// from the built-in table. The resolve endpoint redirects to the CDN and
+/// reports the LFS object id in `X-Linked-Etag`; a plain ETag is not a hash
+/// of the file and is never used.
+pub async fn fetch_published_sha256(url: &str) -> Option<String> {
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .ok()?;
+    let response = client.head(url).send().await.ok()?;
+
+    let etag = response
+        .headers()
+        .get("x-linked-etag")?
+        .to_str()
+        .ok()?
+        .trim_start_matches("W/")
+        .trim_matches('"')
+        .to_ascii_lowercase();
+
+    integrity::is_sha256(&etag).then_some(etag)
+}
+
+/// Download `model` to `dest`, emitting progress events along the way.
+/// The file is checked against `expected_sha256` (when known) and its ggml
+/// header before it is moved into place. Returns the file's SHA-256.
+/// Leaves the `.part` file behind on network failure so the next call can resume.
+pub async fn download_model<R: Runtime>(
+    app: &AppHandle<R>,
+    model: &ModelSpec,
+    dest: &Path,
+    expected_sha256: Option<String>,
+) -> Result<String, String> {
+    let _guard = DownloadGuard::acquire(&model.id)?;
+    let part = part_path(dest);
+    let client = reqwest::Client::new();
+
+    let mut existing = fs::metadata(&part).await.map(|m| m.len()).unwrap_or(0);
+
+    let mut request = client.get(&model.url);
+    if existing > 0 {
+        request = request.header(reqwest::header::RANGE, format!("bytes={}-", existing));
+    }
+
+    let mut response = request
+        .send()
+        .await
+        .map_err(|e| format!("Failed to start download: {}", e))?;
+
+    if response.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+        // The partial file is not a prefix the server recognises, start over
+        fs::remove_file(&part)
+            .await
+            .map_err(|e| format!("Failed to remove partial download: {}", e))?;
+        existing = 0;
+        response = client
+            .get(&model.url)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to start download: {}", e))?;
+    }
+
+    let mut response = response
+        .error_for_status()
+        .map_err(|e| format!("Failed to start download: {}", e))?;
+
+    // Servers that ignore the Range header answer 200 with the full body
+    let resumed = existing > 0 && response.status() == reqwest::StatusCode::PARTIAL_CONTENT;
+    if !resumed {
+        existing = 0;
+    }
+
+    let total = response
+        .content_length()
+        .map(|len| len + existing)
+        .unwrap_or(0);
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(resumed)
+        .truncate(!resumed)
+        .open(&part)
+        .await
+        .map_err(|e| format!("Failed to create model file: {}", e))?;
+
+    let started = Instant::now();
+    let mut last_emit: Option<Instant> = None;
+    let mut downloaded = existing;
+
+    let emit_progress = |downloaded: u64| {
+        let elapsed = started.elapsed().as_secs_f64();
+        let bytes_per_second = if elapsed > 0.0 {
+            ((downloaded - existing) as f64 / elapsed) as u64
+        } else {
+            0
+        };
+        let eta_seconds = if total > 0 && bytes_per_second > 0 {
+            Some(total.saturating_sub(downloaded) / bytes_per_second)
+        } else {
+            None
+        };
+        let progress = if total > 0 {
+            (downloaded as f64 / total as f64 * 100.0) as u32
+        } else {
+            0
+        };
+
+        let _ = app.emit(
+            "whisper-download-progress",
+            DownloadProgress {
+                model_id: &model.id,
+                downloaded,
+                total,
+                progress,
+                bytes_per_second,
+                eta_seconds,
+                resumed,
+            },
+        );
+    };
+
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("Failed to download: {}", e))?
+    {
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| format!("Failed to write model file: {}", e))?;
+        downloaded += chunk.len() as u64;
+
+        if last_emit.is_none_or(|t| t.elapsed() >= PROGRESS_INTERVAL) {
+            emit_progress(downloaded);
+            last_emit = Some(Instant::now());
+        }
+    }
+
+    file.sync_all()
+        .await
+        .map_err(|e| format!("Failed to write model file: {}", e))?;
+    drop(file);
+
+    if total > 0 && downloaded != total {
+        return Err(format!(
+            "Download incomplete: received {} of {} bytes",
+            downloaded, total
+        ));
+    }
+
+    emit_progress(downloaded);
+
+    let _ = app.emit("whisper-download-verifying", &model.id);
+
+    // Hashing a multi-gigabyte file takes a while, keep it off the async runtime
+    let part_clone = part.clone();
+    let sha256 = tauri::async_runtime::spawn_blocking(move || {
+        integrity::verify_file(&part_clone, expected_sha256.as_deref())
+    })
+    .await
+    .map_err(|e| format!("Verification task failed: {}", e))??;
+
+    fs::rename(&part, dest)
+        .await
+        .map_err(|e| format!("Failed to move model into place: {}", e))?;
+
+    Ok(sha256)
+}
@@ -0,0 +1,314 @@
+// Model integrity checks
+// ggml header validation, SHA-256 hashing and quarantine of bad files.
+// Only files that are certainly bad are quarantined, and only inside the
+// app's own models directory; I/O errors are reported and the file is kept.
+
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// "ggml" as stored at the start of every whisper.cpp model file
+const GGML_MAGIC: u32 = 0x6767_6d6c;
+
+/// whisper.cpp stores `ftype + quantization_version * 1000`
+const GGML_QNT_VERSION_FACTOR: i32 = 1000;
+
+/// Hyperparameters from the header of a ggml Whisper model
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GgmlHeader {
+    pub n_vocab: i32,
+    pub n_audio_ctx: i32,
+    pub n_audio_state: i32,
+    pub n_audio_head: i32,
+    pub n_audio_layer: i32,
+    pub n_text_ctx: i32,
+    pub n_text_state: i32,
+    pub n_text_head: i32,
+    pub n_text_layer: i32,
+    pub n_mels: i32,
+    pub ftype: i32,
+}
+
+/// Why a header could not be read
+#[derive(Debug)]
+enum ReadError {
+    /// The file could not be read, which says nothing about its contents
+    Io(String),
+    /// The file is not a usable model
+    Invalid(String),
+}
+
+impl From<ReadError> for String {
+    fn from(error: ReadError) -> Self {
+        match error {
+            ReadError::Io(e) | ReadError::Invalid(e) => e,
+        }
+    }
+}
+
+impl GgmlHeader {
+    /// Read and validate the header of a model file
+    pub fn read(path: &Path) -> Result<Self, String> {
+        Ok(Self::read_checked(path)?)
+    }
+
+    fn read_checked(path: &Path) -> Result<Self, ReadError> {
+        let mut file = File::open(path)
+            .map_err(|e| ReadError::Io(format!("Failed to open model file: {}", e)))?;
+
+        let mut buf = [0u8; 48];
+        file.read_exact(&mut buf).map_err(|e| match e.kind() {
+            std::io::ErrorKind::UnexpectedEof => ReadError::Invalid(format!(
+                "{} is too short to be a Whisper model",
+                path.display()
+            )),
+            _ => ReadError::Io(format!("Failed to read model file: {}", e)),
+        })?;
+
+        let field = |i: usize| {
+            i32::from_le_bytes([buf[i * 4], buf[i * 4 + 1], buf[i * 4 + 2], buf[i * 4 + 3]])
+        };
+
+        if field(0) as u32 != GGML_MAGIC {
+            return Err(ReadError::Invalid(format!(
+                "{} is not a ggml Whisper model (bad magic)",
+                path.display()
+            )));
+        }
+
+        let header = Self {
+            n_vocab: field(1),
+            n_audio_ctx: field(2),
+            n_audio_state: field(3),
+            n_audio_head: field(4),
+            n_audio_layer: field(5),
+            n_text_ctx: field(6),
+            n_text_state: field(7),
+            n_text_head: field(8),
+            n_text_layer: field(9),
+            n_mels: field(10),
+            ftype: field(11),
+        };
+
+        if header.n_vocab <= 0 || header.n_audio_layer <= 0 || header.n_text_layer <= 0 {
+            return Err(ReadError::Invalid(format!(
+                "{} has a corrupt model header",
+                path.display()
+            )));
+        }
+
+        Ok(header)
+    }
+
+    /// English-only models have a smaller vocabulary than multilingual ones
+    pub fn is_multilingual(&self) -> bool {
+        self.n_vocab >= 51865
+    }
+
+    /// Weight format, e.g. `f16` or `q5_1`
+    pub fn quantization(&self) -> &'static str {
+        match self.ftype % GGML_QNT_VERSION_FACTOR {
+            0 => "f32",
+            1 => "f16",
+            2 => "q4_0",
+            3 => "q4_1",
+            7 => "q8_0",
+            8 => "q5_0",
+            9 => "q5_1",
+            10 => "q2_k",
+            11 => "q3_k",
+            12 => "q4_k",
+            13 => "q5_k",
+            14 => "q6_k",
+            _ => "unknown",
+        }
+    }
+
+    /// Model size class derived from the encoder depth
+    pub fn model_type(&self) -> &'static str {
+        match self.n_audio_layer {
+            4 => "tiny",
+            6 => "base",
+            12 => "small",
+            24 => "medium",
+            32 => "large",
+            _ => "unknown",
+        }
+    }
+}
+
+/// Hex-encoded SHA-256 of a file
+pub fn sha256_file(path: &Path) -> Result<String, String> {
+    let mut file = File::open(path)
+        .map_err(|e| format!("Failed to open model file: {}", e))?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 1024 * 1024];
+
+    loop {
+        let n = file.read(&mut buf)
+            .map_err(|e| format!("Failed to read model file: {}", e))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// True if `s` looks like a hex-encoded SHA-256
+pub fn is_sha256(s: &str) -> bool {
+    s.len() == 64 && s.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Move a bad model file into `<models>/quarantine` so it is never loaded
+/// or resumed again. Returns the new location.
+pub fn quarantine(path: &Path) -> Result<PathBuf, String> {
+    let dir = path
+        .parent()
+        .ok_or("Invalid model path")?
+        .join("quarantine");
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create quarantine directory: {}", e))?;
+
+    let stamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}", stamp));
+    let target = dir.join(name);
+
+    std::fs::rename(path, &target)
+        .map_err(|e| format!("Failed to quarantine {}: {}", path.display(), e))?;
+    Ok(target)
+}
+
+/// Check a model file against its header and, when known, its hash.
+/// Bad files are quarantined and reported with a descriptive error.
+pub fn verify_file(path: &Path, expected_sha256: Option<&str>) -> Result<String, String> {
+    check_header(path)?;
+
+    let actual = sha256_file(path)?;
+    if let Some(expected) = expected_sha256 {
+        if !actual.eq_ignore_ascii_case(expected) {
+            let reason = format!(
+                "Checksum mismatch for {}: expected {}, got {}",
+                path.display(),
+                expected,
+                actual
+            );
+            return Err(quarantine_with_reason(path, &reason));
+        }
+    }
+
+    Ok(actual)
+}
+
+/// Whether `path` is directly inside the app's models directory
+fn in_models_dir(path: &Path) -> bool {
+    let (Ok(dir), Some(parent)) = (super::catalog::get_models_dir(), path.parent()) else {
+        return false;
+    };
+    matches!(
+        (dir.canonicalize(), parent.canonicalize()),
+        (Ok(dir), Ok(parent)) if dir == parent
+    )
+}
+
+fn quarantine_with_reason(path: &Path, reason: &str) -> String {
+    // Files in the shared directory belong to someone else
+    if !in_models_dir(path) {
+        return format!("{}. The file was left in place", reason);
+    }
+    match quarantine(path) {
+        Ok(target) => format!("{}. The file was moved to {}", reason, target.display()),
+        Err(e) => format!("{}. {}", reason, e),
+    }
+}
+
+/// Header check used before loading; quarantines files that fail it
+pub fn check_header(path: &Path) -> Result<GgmlHeader, String> {
+    GgmlHeader::read_checked(path).map_err(|e| match e {
+        ReadError::Io(e) => e,
+        ReadError::Invalid(e) => quarantine_with_reason(path, &e),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A model header with the given magic and hyperparameters
+    fn header_bytes(magic: u32, fields: [i32; 11]) -> Vec<u8> {
+        let mut bytes = magic.to_le_bytes().to_vec();
+        for field in fields {
+            bytes.extend(field.to_le_bytes());
+        }
+        bytes
+    }
+
+    const BASE_EN: [i32; 11] = [51864, 1500, 512, 8, 6, 448, 512, 8, 6, 80, 1];
+
+    fn write_model(name: &str, bytes: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("ggml-{}-{}.bin", name, std::process::id()));
+        std::fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    #[test]
+    fn reads_header_fields() {
+        let mut bytes = header_bytes(GGML_MAGIC, BASE_EN);
+        bytes.extend([0u8; 16]);
+        let path = write_model("header", &bytes);
+        let header = GgmlHeader::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(header.n_vocab, 51864);
+        assert_eq!(header.n_mels, 80);
+        assert_eq!(header.model_type(), "base");
+        assert_eq!(header.quantization(), "f16");
+        assert!(!header.is_multilingual());
+    }
+
+    #[test]
+    fn reads_quantization_with_version() {
+        let mut fields = BASE_EN;
+        fields[10] = 2009;
+        let path = write_model("quantized", &header_bytes(GGML_MAGIC, fields));
+        let header = GgmlHeader::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(header.quantization(), "q5_1");
+    }
+
+    #[test]
+    fn bad_files_are_invalid() {
+        let cases = [
+            ("magic", header_bytes(0x1234_5678, BASE_EN)),
+            ("short", header_bytes(GGML_MAGIC, BASE_EN)[..20].to_vec()),
+            ("layers", {
+                let mut fields = BASE_EN;
+                fields[4] = 0;
+                header_bytes(GGML_MAGIC, fields)
+            }),
+        ];
+        for (name, bytes) in cases {
+            let path = write_model(name, &bytes);
+            let result = GgmlHeader::read_checked(&path);
+            std::fs::remove_file(&path).unwrap();
+            assert!(matches!(result, Err(ReadError::Invalid(_))), "{}", name);
+        }
+    }
+
+    #[test]
+    fn unreadable_files_are_not_invalid() {
+        let path = std::env::temp_dir().join("ggml-missing-model.bin");
+        assert!(matches!(
+            GgmlHeader::read_checked(&path),
+            Err(ReadError::Io(_))
+        ));
+        assert!(check_header(&path).is_err());
+    }
+}
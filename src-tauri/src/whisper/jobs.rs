@@ -0,0 +1,251 @@
+// Background file transcription
+// A file is decoded, split into chunks at pauses and transcribed on the
+// inference worker at batch priority. Chunks cut outside a pause overlap
+// and keep only their side of the cut; chunks without speech are skipped.
+// Progress is reported with `transcription-progress`, which carries the
+// segments so far and which of them are new; a job ends with
+// `transcription-complete`, `transcription-cancelled` or `transcription-error`.
+
+use super::native::{
+    join_segments, offset_segment, resolve_options, transcribe_with_hooks, DecodeHooks, Segment,
+    TranscriptionResult,
+};
+use super::options::TranscribeOptions;
+use super::worker::{Job, Priority};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{command, AppHandle, Emitter, Runtime};
+
+const SAMPLE_RATE: usize = crate::audio::WHISPER_SAMPLE_RATE as usize;
+/// Whisper decodes 30 second windows, longer chunks gain nothing
+const CHUNK_SAMPLES: usize = SAMPLE_RATE * 30;
+/// How far chunks overlap where a cut misses a pause
+const OVERLAP_SAMPLES: usize = SAMPLE_RATE * 2;
+
+/// A file job and the segments transcribed so far
+struct FileJob {
+    job: Arc<Job>,
+    segments: Mutex<Vec<Segment>>,
+    /// How many segments the last progress event carried
+    reported: AtomicUsize,
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProgressEvent<'a> {
+    job_id: u64,
+    percent: u32,
+    /// Every segment transcribed so far
+    segments: &'a [Segment],
+    /// The tail of `segments` added since the previous progress event
+    new_segments: &'a [Segment],
+}
+
+impl FileJob {
+    /// Record progress and emit it, skipping updates that change nothing
+    fn report<R: Runtime>(&self, app: &AppHandle<R>, percent: u32) {
+        if !self.job.advance(percent) {
+            return;
+        }
+        if let Ok(segments) = self.segments.lock() {
+            let new = self.reported.swap(segments.len(), Ordering::SeqCst);
+            let _ = app.emit(
+                "transcription-progress",
+                ProgressEvent {
+                    job_id: self.job.id,
+                    percent,
+                    segments: &segments,
+                    new_segments: &segments[new..],
+                },
+            );
+        }
+    }
+}
+
+fn samples_to_ms(samples: usize) -> i64 {
+    (samples * 1000 / SAMPLE_RATE) as i64
+}
+
+fn run_job<R: Runtime>(
+    app: &AppHandle<R>,
+    file_job: &Arc<FileJob>,
+    path: PathBuf,
+    mut options: TranscribeOptions,
+) -> Result<TranscriptionResult, String> {
+    let vad = crate::settings::load()?.vad;
+    let audio = crate::audio::decode_file(&path)?;
+    // Cuts in pauses keep words whole; the others overlap the next chunk
+    let pieces = crate::vad::split_at_pauses(&audio, CHUNK_SAMPLES, OVERLAP_SAMPLES, &vad);
+    let chunks = pieces.len() as u32;
+    let mut language = None;
+    let mut language_probability = None;
+
+    for (index, piece) in pieces.into_iter().enumerate() {
+        if file_job.job.is_cancelled() {
+            return Err("Transcription cancelled".to_string());
+        }
+
+        // Where chunks overlap, each keeps the segments centred on its side
+        // of the cut
+        let keep_from = if piece.keep.start > piece.range.start {
+            samples_to_ms(piece.keep.start)
+        } else {
+            i64::MIN
+        };
+        let keep_until = if piece.keep.end < piece.range.end {
+            samples_to_ms(piece.keep.end)
+        } else {
+            i64::MAX
+        };
+        let range = piece.range;
+
+        // Whisper makes up text for silence, so silent chunks are skipped
+        let range = if vad.trim_silence {
+            match crate::vad::trim_silence(&audio[range.clone()], &vad) {
+                Some(speech) => range.start + speech.start..range.start + speech.end,
+                None => {
+                    file_job.report(app, (index as u32 + 1) * 100 / chunks);
+                    continue;
+                }
+            }
+        } else {
+            range
+        };
+        let offset_ms = samples_to_ms(range.start);
+
+        // The end of the previous chunk gives Whisper context across the cut
+        let prompt = file_job
+            .segments
+            .lock()
+            .map_err(|_| "Lock poisoned")?
+            .last()
+            .map(|s| s.text.clone());
+
+        let chunk = audio[range].to_vec();
+        let chunk_options = options.clone();
+        let progress_app = app.clone();
+        let progress_job = file_job.clone();
+        let abort_job = file_job.job.clone();
+
+        let result = file_job
+            .job
+            .run(move |ctx| {
+                let hooks = DecodeHooks {
+                    progress: Some(Box::new(move |percent: i32| {
+                        let percent = percent.clamp(0, 100) as u32;
+                        let overall = (index as u32 * 100 + percent) / chunks;
+                        progress_job.report(&progress_app, overall.min(99));
+                    })),
+                    abort: Some(Box::new(move || abort_job.is_cancelled())),
+                    no_speech_prob: true,
+                };
+                transcribe_with_hooks(ctx, &chunk, &chunk_options, prompt.as_deref(), hooks)
+            })
+            .map_err(|e| {
+                if file_job.job.is_cancelled() {
+                    "Transcription cancelled".to_string()
+                } else {
+                    e
+                }
+            })?;
+
+        // Detect the language once instead of per chunk
+        if options.is_auto_language() {
+            if let Some(detected) = result.language.clone() {
+                options.language = detected;
+                language_probability = result.language_probability;
+            }
+        }
+        language = result.language;
+
+        {
+            let mut segments = file_job.segments.lock().map_err(|_| "Lock poisoned")?;
+            let last_end = segments.last().map(|s| s.end_ms).unwrap_or(i64::MIN);
+            segments.extend(
+                result
+                    .segments
+                    .into_iter()
+                    .map(|s| offset_segment(s, offset_ms))
+                    .filter(|s| {
+                        let middle = (s.start_ms + s.end_ms) / 2;
+                        middle >= keep_from.max(last_end) && middle < keep_until
+                    }),
+            );
+        }
+
+        file_job.report(app, (index as u32 + 1) * 100 / chunks);
+    }
+
+    let segments = file_job
+        .segments
+        .lock()
+        .map_err(|_| "Lock poisoned")?
+        .clone();
+    Ok(TranscriptionResult {
+        text: join_segments(&segments),
+        segments,
+        language,
+        language_probability,
+    })
+}
+
+/// Transcribe an audio file in the background and return the job id
+#[command]
+pub fn start_file_transcription<R: Runtime>(
+    app: AppHandle<R>,
+    file_path: String,
+    options: Option<TranscribeOptions>,
+) -> Result<u64, String> {
+    let path = PathBuf::from(&file_path);
+    if !path.is_file() {
+        return Err(format!("Audio file not found: {}", file_path));
+    }
+    let options = resolve_options(options)?;
+
+    let file_job = Arc::new(FileJob {
+        job: Job::register(Priority::Batch)?,
+        segments: Mutex::new(Vec::new()),
+        reported: AtomicUsize::new(0),
+    });
+    let job_id = file_job.job.id;
+
+    let thread_job = file_job.clone();
+    std::thread::Builder::new()
+        .name(format!("whisper-job-{}", job_id))
+        .spawn(move || {
+            let file_job = thread_job;
+            let result = run_job(&app, &file_job, path, options);
+            file_job.job.finish(&result);
+
+            let _ = match result {
+                Ok(result) => app.emit(
+                    "transcription-complete",
+                    serde_json::json!({
+                        "jobId": job_id,
+                        "result": result,
+                    }),
+                ),
+                Err(_) if file_job.job.is_cancelled() => app.emit(
+                    "transcription-cancelled",
+                    serde_json::json!({
+                        "jobId": job_id,
+                    }),
+                ),
+                Err(e) => app.emit(
+                    "transcription-error",
+                    serde_json::json!({
+                        "jobId": job_id,
+                        "error": e,
+                    }),
+                ),
+            };
+        })
+        .map_err(|e| {
+            let error = format!("Failed to start transcription: {}", e);
+            file_job.job.finish::<()>(&Err(error.clone()));
+            error
+        })?;
+
+    Ok(job_id)
+}
@@ -0,0 +1,140 @@
+// Options passed from the frontend to native transcription
+
+#![cfg_attr(not(feature = "native-whisper"), allow(dead_code))]
+
+use serde::{Deserialize, Serialize};
+
+/// Detect the spoken language instead of assuming one
+pub const AUTO_LANGUAGE: &str = "auto";
+
+/// whisper.cpp keeps at most half of the 448-token text context for the prompt
+pub const MAX_PROMPT_TOKENS: u32 = 224;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TranscribeOptions {
+    /// Language code (`en`, `de`, `es`, ...) or `auto`
+    pub language: String,
+    /// Translate the speech to English instead of transcribing it
+    pub translate: bool,
+    /// Include per-word timestamps in detailed results
+    pub word_timestamps: bool,
+
+    /// Beam search width; 1 decodes greedily
+    pub beam_size: u32,
+    /// Candidates sampled per step when decoding greedily with temperature
+    pub best_of: u32,
+    pub temperature: f32,
+    /// Temperature step used to retry segments that fail the quality checks,
+    /// 0 disables the fallback
+    pub temperature_inc: f32,
+    /// Worker threads, `None` lets whisper.cpp pick
+    pub threads: Option<u32>,
+    /// Text that primes the decoder, e.g. expected vocabulary or style
+    pub initial_prompt: Option<String>,
+    /// Probability above which a window is treated as silence
+    pub no_speech_threshold: f32,
+    pub suppress_blank: bool,
+    /// Suppress tokens like `[MUSIC]` or `(laughs)`
+    pub suppress_non_speech_tokens: bool,
+    /// Maximum segment length in characters, 0 for no limit
+    pub max_segment_len: u32,
+    /// Tokens of the prompt reserved for the user vocabulary. Long prompts
+    /// hurt accuracy, so words beyond this budget are left out.
+    pub vocabulary_token_budget: u32,
+}
+
+impl Default for TranscribeOptions {
+    fn default() -> Self {
+        Self {
+            language: "en".to_string(),
+            translate: false,
+            word_timestamps: false,
+            beam_size: 1,
+            best_of: 1,
+            temperature: 0.0,
+            temperature_inc: 0.2,
+            threads: None,
+            initial_prompt: None,
+            no_speech_threshold: 0.6,
+            suppress_blank: true,
+            suppress_non_speech_tokens: false,
+            max_segment_len: 0,
+            vocabulary_token_budget: 64,
+        }
+    }
+}
+
+impl TranscribeOptions {
+    pub fn is_auto_language(&self) -> bool {
+        self.language.is_empty() || self.language.eq_ignore_ascii_case(AUTO_LANGUAGE)
+    }
+
+    /// Reject values whisper.cpp would misbehave with
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=16).contains(&self.beam_size) {
+            return Err("Beam size must be between 1 and 16".to_string());
+        }
+        if !(1..=16).contains(&self.best_of) {
+            return Err("Best-of must be between 1 and 16".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.temperature) || !(0.0..=1.0).contains(&self.temperature_inc) {
+            return Err("Temperature and temperature step must be between 0 and 1".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.no_speech_threshold) {
+            return Err("No-speech threshold must be between 0 and 1".to_string());
+        }
+        if self.vocabulary_token_budget > MAX_PROMPT_TOKENS {
+            return Err(format!(
+                "Vocabulary token budget cannot exceed {} tokens",
+                MAX_PROMPT_TOKENS
+            ));
+        }
+        if self.threads == Some(0) {
+            return Err("Thread count must be at least 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_are_valid() {
+        assert!(TranscribeOptions::default().validate().is_ok());
+    }
+
+    #[test]
+    fn rejects_out_of_range_values() {
+        let cases: [fn(&mut TranscribeOptions); 7] = [
+            |o| o.beam_size = 0,
+            |o| o.best_of = 17,
+            |o| o.temperature = 1.5,
+            |o| o.temperature_inc = -0.1,
+            |o| o.no_speech_threshold = 2.0,
+            |o| o.vocabulary_token_budget = MAX_PROMPT_TOKENS + 1,
+            |o| o.threads = Some(0),
+        ];
+        for (i, change) in cases.iter().enumerate() {
+            let mut options = TranscribeOptions::default();
+            change(&mut options);
+            assert!(options.validate().is_err(), "case {}", i);
+        }
+    }
+
+    #[test]
+    fn accepts_the_range_limits() {
+        let options = TranscribeOptions {
+            beam_size: 16,
+            best_of: 1,
+            temperature: 1.0,
+            temperature_inc: 0.0,
+            vocabulary_token_budget: MAX_PROMPT_TOKENS,
+            threads: Some(1),
+            ..TranscribeOptions::default()
+        };
+        assert!(options.validate().is_ok());
+    }
+}
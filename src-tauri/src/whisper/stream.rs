@@ -0,0 +1,468 @@
+// Streaming transcription sessions
+// Audio is pushed in small chunks and decoded over a sliding window on a
+// per-session thread. Each decode emits `transcription-partial` with the
+// running transcript; `finish_stream` emits `transcription-final`.
+// Sessions that get no audio for `IDLE_TIMEOUT` are closed, so one the
+// frontend lost track of doesn't keep its thread and audio forever.
+
+use super::native::{join_segments, resolve_options, transcribe, Segment, TranscriptionResult};
+use super::options::TranscribeOptions;
+use super::worker::{self, Priority};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{command, AppHandle, Emitter, Runtime};
+
+const SAMPLE_RATE: usize = crate::audio::WHISPER_SAMPLE_RATE as usize;
+/// Decode again after this much new audio
+const STEP_SAMPLES: usize = SAMPLE_RATE;
+/// Commit finished segments once the window grows this long
+const WINDOW_SAMPLES: usize = SAMPLE_RATE * 10;
+/// Trailing audio shorter than this is not worth a final decode
+const MIN_FINAL_SAMPLES: usize = SAMPLE_RATE / 4;
+/// Longest window kept while decodes fail, the most Whisper decodes at once
+const MAX_WINDOW_SAMPLES: usize = SAMPLE_RATE * 30;
+/// Close a session after this long without any message
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+enum StreamMessage {
+    Audio(Vec<f32>),
+    Finish(Sender<Result<String, String>>),
+    Cancel,
+}
+
+static SESSIONS: Mutex<Option<HashMap<u64, Sender<StreamMessage>>>> = Mutex::new(None);
+static NEXT_SESSION_ID: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TranscriptionEvent<'a> {
+    session_id: u64,
+    text: &'a str,
+}
+
+struct StreamSession {
+    options: TranscribeOptions,
+    /// Audio not yet committed; starts at the last uncommitted segment
+    window: Vec<f32>,
+    /// Samples added since the last decode
+    pending: usize,
+    committed: Vec<String>,
+    /// Current guess for the uncommitted part of the window
+    hypothesis: String,
+}
+
+impl StreamSession {
+    fn new(options: TranscribeOptions) -> Self {
+        Self {
+            options,
+            window: Vec::new(),
+            pending: 0,
+            committed: Vec::new(),
+            hypothesis: String::new(),
+        }
+    }
+
+    fn push(&mut self, chunk: Vec<f32>) {
+        self.pending += chunk.len();
+        self.window.extend(chunk);
+    }
+
+    fn commit(&mut self, segments: &[Segment]) {
+        self.committed.extend(
+            segments
+                .iter()
+                .map(|s| s.text.trim().to_string())
+                .filter(|t| !t.is_empty()),
+        );
+    }
+
+    /// Decode the current window
+    fn decode(&mut self, is_final: bool) -> Result<(), String> {
+        // A failed decode waits for the next step too instead of retrying
+        // on every chunk
+        self.pending = 0;
+        let prompt = self.committed.last().cloned();
+        let audio = self.window.clone();
+        let options = self.options.clone();
+        let result = worker::run_once(Priority::Live, move |ctx| {
+            transcribe(ctx, &audio, &options, prompt.as_deref())
+        });
+        self.apply(result, is_final)
+    }
+
+    /// Take in the result of decoding the window. Once it reaches
+    /// `WINDOW_SAMPLES` every segment but the last is committed and its audio
+    /// dropped; the last segment may be cut off, so its audio stays and is
+    /// decoded again together with the next chunks.
+    fn apply(
+        &mut self,
+        result: Result<TranscriptionResult, String>,
+        is_final: bool,
+    ) -> Result<(), String> {
+        let segments = match result {
+            Ok(result) => {
+                // Keep the detected language for the rest of the session
+                // instead of re-detecting it on every short window
+                if self.options.is_auto_language() {
+                    if let Some(language) = result.language {
+                        self.options.language = language;
+                    }
+                }
+                result.segments
+            }
+            Err(e) => {
+                self.cap_window();
+                return Err(e);
+            }
+        };
+
+        if is_final {
+            self.commit(&segments);
+            self.window.clear();
+            self.hypothesis.clear();
+        } else if self.window.len() >= WINDOW_SAMPLES {
+            match segments.split_last() {
+                Some((last, done)) if !done.is_empty() => {
+                    self.commit(done);
+                    let cut = last.start_ms.max(0) as usize * SAMPLE_RATE / 1000;
+                    self.window.drain(..cut.min(self.window.len()));
+                    self.hypothesis = last.text.trim().to_string();
+                }
+                _ => {
+                    // One segment spans the whole window, commit it as is
+                    self.commit(&segments);
+                    self.window.clear();
+                    self.hypothesis.clear();
+                }
+            }
+        } else {
+            self.hypothesis = join_segments(&segments);
+        }
+
+        Ok(())
+    }
+
+    /// Drop the oldest audio while decodes fail, so the window doesn't grow
+    /// without bound. Speech in the dropped audio is lost.
+    fn cap_window(&mut self) {
+        if self.window.len() > MAX_WINDOW_SAMPLES {
+            let excess = self.window.len() - MAX_WINDOW_SAMPLES;
+            self.window.drain(..excess);
+            self.hypothesis.clear();
+        }
+    }
+
+    fn text(&self) -> String {
+        let mut parts = self.committed.clone();
+        if !self.hypothesis.is_empty() {
+            parts.push(self.hypothesis.clone());
+        }
+        parts.join(" ")
+    }
+}
+
+fn run_session<R: Runtime>(
+    app: AppHandle<R>,
+    session_id: u64,
+    options: TranscribeOptions,
+    rx: Receiver<StreamMessage>,
+) {
+    let mut session = StreamSession::new(options);
+
+    loop {
+        let message = match next_message(&rx, session_id, IDLE_TIMEOUT) {
+            Ok(message) => message,
+            Err(RecvTimeoutError::Timeout) => {
+                let _ = app.emit(
+                    "transcription-error",
+                    serde_json::json!({
+                        "sessionId": session_id,
+                        "error": format!("Stream session {} timed out", session_id),
+                    }),
+                );
+                return;
+            }
+            Err(RecvTimeoutError::Disconnected) => return,
+        };
+
+        let mut finish = None;
+        match message {
+            StreamMessage::Audio(chunk) => session.push(chunk),
+            StreamMessage::Finish(reply) => finish = Some(reply),
+            StreamMessage::Cancel => return,
+        }
+
+        // Take everything that queued up during the last decode so a slow
+        // machine catches up instead of falling further behind
+        while finish.is_none() {
+            match rx.try_recv() {
+                Ok(StreamMessage::Audio(chunk)) => session.push(chunk),
+                Ok(StreamMessage::Finish(reply)) => finish = Some(reply),
+                Ok(StreamMessage::Cancel) => return,
+                Err(_) => break,
+            }
+        }
+
+        if let Some(reply) = finish {
+            let result = if session.window.len() >= MIN_FINAL_SAMPLES {
+                session.decode(true)
+            } else {
+                Ok(())
+            }
+            .map(|_| session.text());
+
+            if let Ok(text) = &result {
+                let _ = app.emit("transcription-final", TranscriptionEvent { session_id, text });
+            }
+            let _ = reply.send(result);
+            return;
+        }
+
+        if session.pending >= STEP_SAMPLES {
+            match session.decode(false) {
+                Ok(()) => {
+                    let text = session.text();
+                    let _ = app.emit(
+                        "transcription-partial",
+                        TranscriptionEvent { session_id, text: &text },
+                    );
+                }
+                Err(e) => {
+                    let _ = app.emit("transcription-error", serde_json::json!({
+                        "sessionId": session_id,
+                        "error": e,
+                    }));
+                }
+            }
+        }
+    }
+}
+
+/// Wait for the next message, giving up with `Timeout` once the session has
+/// been idle for `timeout` and is removed. A session already taken out by
+/// `finish_stream` or `cancel_stream` is about to get its last message, so
+/// it keeps waiting.
+fn next_message(
+    rx: &Receiver<StreamMessage>,
+    session_id: u64,
+    timeout: Duration,
+) -> Result<StreamMessage, RecvTimeoutError> {
+    loop {
+        match rx.recv_timeout(timeout) {
+            Err(RecvTimeoutError::Timeout) if remove_session(session_id).is_none() => continue,
+            other => return other,
+        }
+    }
+}
+
+fn remove_session(session_id: u64) -> Option<Sender<StreamMessage>> {
+    SESSIONS
+        .lock()
+        .ok()?
+        .as_mut()
+        .and_then(|sessions| sessions.remove(&session_id))
+}
+
+fn session_sender(session_id: u64) -> Result<Sender<StreamMessage>, String> {
+    SESSIONS
+        .lock()
+        .map_err(|_| "Lock poisoned")?
+        .as_ref()
+        .and_then(|sessions| sessions.get(&session_id).cloned())
+        .ok_or_else(|| format!("Unknown stream session {}", session_id))
+}
+
+/// Start a streaming transcription session and return its id
+#[command]
+pub fn start_stream<R: Runtime>(
+    app: AppHandle<R>,
+    options: Option<TranscribeOptions>,
+) -> Result<u64, String> {
+    let options = resolve_options(options)?;
+    let session_id = NEXT_SESSION_ID.fetch_add(1, Ordering::Relaxed);
+    let (tx, rx) = mpsc::channel();
+
+    std::thread::Builder::new()
+        .name(format!("whisper-stream-{}", session_id))
+        .spawn(move || run_session(app, session_id, options, rx))
+        .map_err(|e| format!("Failed to start stream: {}", e))?;
+
+    SESSIONS
+        .lock()
+        .map_err(|_| "Lock poisoned")?
+        .get_or_insert_with(HashMap::new)
+        .insert(session_id, tx);
+
+    Ok(session_id)
+}
+
+/// Append 16kHz mono audio to a streaming session
+#[command]
+pub fn push_audio(session_id: u64, chunk: Vec<f32>) -> Result<(), String> {
+    session_sender(session_id)?
+        .send(StreamMessage::Audio(chunk))
+        .map_err(|_| format!("Stream session {} has ended", session_id))
+}
+
+/// Decode the remaining audio, end the session and return the full transcript
+#[command]
+pub async fn finish_stream(session_id: u64) -> Result<String, String> {
+    let sender = remove_session(session_id)
+        .ok_or_else(|| format!("Unknown stream session {}", session_id))?;
+
+    let (reply_tx, reply_rx) = mpsc::channel();
+    sender
+        .send(StreamMessage::Finish(reply_tx))
+        .map_err(|_| format!("Stream session {} has ended", session_id))?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        reply_rx
+            .recv()
+            .map_err(|_| format!("Stream session {} ended unexpectedly", session_id))?
+    })
+    .await
+    .map_err(|e| format!("Stream task failed: {}", e))?
+}
+
+/// End a session without decoding the rest of its audio
+#[command]
+pub fn cancel_stream(session_id: u64) -> Result<(), String> {
+    let sender = remove_session(session_id)
+        .ok_or_else(|| format!("Unknown stream session {}", session_id))?;
+    let _ = sender.send(StreamMessage::Cancel);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(start_ms: i64, end_ms: i64, text: &str) -> Segment {
+        Segment {
+            start_ms,
+            end_ms,
+            text: text.to_string(),
+            avg_logprob: 0.0,
+            no_speech_prob: None,
+            words: None,
+        }
+    }
+
+    fn decoded(segments: Vec<Segment>) -> Result<TranscriptionResult, String> {
+        Ok(TranscriptionResult {
+            text: join_segments(&segments),
+            segments,
+            language: Some("de".to_string()),
+            language_probability: None,
+        })
+    }
+
+    fn session(seconds: usize) -> StreamSession {
+        let mut session = StreamSession::new(TranscribeOptions {
+            language: "auto".to_string(),
+            ..TranscribeOptions::default()
+        });
+        session.push(vec![0.0; SAMPLE_RATE * seconds]);
+        session
+    }
+
+    #[test]
+    fn short_windows_only_update_the_hypothesis() {
+        let mut session = session(3);
+        session
+            .apply(
+                decoded(vec![
+                    segment(0, 1500, "Hello"),
+                    segment(1500, 3000, "there"),
+                ]),
+                false,
+            )
+            .unwrap();
+        assert!(session.committed.is_empty());
+        assert_eq!(session.window.len(), SAMPLE_RATE * 3);
+        assert_eq!(session.text(), "Hello there");
+        assert_eq!(session.options.language, "de");
+    }
+
+    #[test]
+    fn full_windows_commit_all_but_the_last_segment() {
+        let mut session = session(10);
+        session
+            .apply(
+                decoded(vec![
+                    segment(0, 4000, " One."),
+                    segment(4000, 8000, " Two."),
+                    segment(8000, 10000, " Thr"),
+                ]),
+                false,
+            )
+            .unwrap();
+        assert_eq!(session.committed, ["One.", "Two."]);
+        // The window slides to the start of the last segment
+        assert_eq!(session.window.len(), SAMPLE_RATE * 2);
+        assert_eq!(session.text(), "One. Two. Thr");
+
+        session.push(vec![0.0; SAMPLE_RATE]);
+        session
+            .apply(decoded(vec![segment(0, 3000, " Three.")]), true)
+            .unwrap();
+        assert!(session.window.is_empty());
+        assert_eq!(session.text(), "One. Two. Three.");
+    }
+
+    #[test]
+    fn a_window_spanning_segment_is_committed_whole() {
+        let mut session = session(10);
+        session
+            .apply(decoded(vec![segment(0, 10000, " Long sentence")]), false)
+            .unwrap();
+        assert_eq!(session.committed, ["Long sentence"]);
+        assert!(session.window.is_empty());
+    }
+
+    #[test]
+    fn failed_decodes_cap_the_window() {
+        let mut session = session(20);
+        session.hypothesis = "partial".to_string();
+        assert!(session.apply(Err("failed".to_string()), false).is_err());
+        assert_eq!(session.window.len(), SAMPLE_RATE * 20);
+        assert_eq!(session.hypothesis, "partial");
+
+        session.push(vec![0.0; SAMPLE_RATE * 15]);
+        assert!(session.apply(Err("failed".to_string()), false).is_err());
+        assert_eq!(session.window.len(), MAX_WINDOW_SAMPLES);
+        assert!(session.hypothesis.is_empty());
+    }
+
+    #[test]
+    fn idle_sessions_are_removed() {
+        let session_id = NEXT_SESSION_ID.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = mpsc::channel();
+        SESSIONS
+            .lock()
+            .unwrap()
+            .get_or_insert_with(HashMap::new)
+            .insert(session_id, tx);
+
+        let result = next_message(&rx, session_id, Duration::from_millis(20));
+        assert!(matches!(result, Err(RecvTimeoutError::Timeout)));
+        assert!(session_sender(session_id).is_err());
+    }
+
+    #[test]
+    fn taken_sessions_wait_for_their_last_message() {
+        let session_id = NEXT_SESSION_ID.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = mpsc::channel();
+        let sender = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(60));
+            tx.send(StreamMessage::Cancel).unwrap();
+        });
+
+        let result = next_message(&rx, session_id, Duration::from_millis(20));
+        assert!(matches!(result, Ok(StreamMessage::Cancel)));
+        sender.join().unwrap();
+    }
+}
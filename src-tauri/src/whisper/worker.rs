@@ -0,0 +1,398 @@
+// Inference worker
+// A single thread runs all Whisper inference. Work waits in a queue where
+// live dictation always goes ahead of batch work such as file transcription.
+// File jobs queue one chunk at a time, so dictation waits for at most one
+// chunk instead of the whole file. Only batch jobs count towards the queue
+// limit, and one-off tasks are forgotten once they finish.
+
+use super::native::loaded_model;
+use std::collections::{HashMap, VecDeque};
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use tauri::command;
+use whisper_rs::WhisperContext;
+
+/// Batch jobs that may be queued or running at once
+const MAX_BATCH_JOBS: usize = 16;
+/// Finished file jobs kept around for status queries
+const MAX_FINISHED_JOBS: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Priority {
+    /// Dictation the user is waiting on
+    Live,
+    /// Files and other work that can wait
+    Batch,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum JobState {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl JobState {
+    fn is_finished(self) -> bool {
+        matches!(self, Self::Completed | Self::Failed | Self::Cancelled)
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobStatus {
+    pub id: u64,
+    pub priority: Priority,
+    pub state: JobState,
+    pub percent: u32,
+    /// Tasks ahead of this job's next task, for queued jobs
+    pub queue_position: Option<usize>,
+    pub error: Option<String>,
+}
+
+/// A unit of work tracked from submission until it finishes
+pub(super) struct Job {
+    pub id: u64,
+    pub priority: Priority,
+    state: Mutex<(JobState, Option<String>)>,
+    cancelled: AtomicBool,
+    percent: AtomicU32,
+}
+
+type Task = Box<dyn FnOnce(Option<&WhisperContext>) + Send>;
+
+#[derive(Default)]
+struct Queue {
+    live: VecDeque<(Arc<Job>, Task)>,
+    batch: VecDeque<(Arc<Job>, Task)>,
+}
+
+impl Queue {
+    fn pop(&mut self) -> Option<(Arc<Job>, Task)> {
+        self.live.pop_front().or_else(|| self.batch.pop_front())
+    }
+
+    fn position(&self, job_id: u64) -> Option<usize> {
+        self.live
+            .iter()
+            .chain(self.batch.iter())
+            .position(|(job, _)| job.id == job_id)
+    }
+}
+
+static JOBS: Mutex<Option<HashMap<u64, Arc<Job>>>> = Mutex::new(None);
+static NEXT_JOB_ID: AtomicU64 = AtomicU64::new(1);
+static QUEUE: Mutex<Option<Queue>> = Mutex::new(None);
+static QUEUE_READY: Condvar = Condvar::new();
+static WORKER: OnceLock<Result<(), String>> = OnceLock::new();
+
+fn start_worker() -> Result<(), String> {
+    WORKER
+        .get_or_init(|| {
+            std::thread::Builder::new()
+                .name("whisper-worker".to_string())
+                .spawn(run_worker)
+                .map(|_| ())
+                .map_err(|e| format!("Failed to start inference worker: {}", e))
+        })
+        .clone()
+}
+
+fn run_worker() {
+    loop {
+        let (job, task) = {
+            let Ok(mut queue) = QUEUE.lock() else {
+                return;
+            };
+            loop {
+                if let Some(next) = queue.get_or_insert_with(Queue::default).pop() {
+                    break next;
+                }
+                queue = match QUEUE_READY.wait(queue) {
+                    Ok(queue) => queue,
+                    Err(_) => return,
+                };
+            }
+        };
+
+        // Dropping the task wakes the submitter with a cancellation error
+        if job.is_cancelled() {
+            continue;
+        }
+
+        job.set_state(JobState::Running, None);
+        let model = loaded_model();
+        // A panicking task fails its job, not every job after it
+        let _ = std::panic::catch_unwind(AssertUnwindSafe(|| task(model.as_deref())));
+    }
+}
+
+impl Job {
+    /// Register a new job, failing when too many batch jobs are already waiting
+    pub fn register(priority: Priority) -> Result<Arc<Job>, String> {
+        start_worker()?;
+
+        let mut jobs = JOBS.lock().map_err(|_| "Lock poisoned")?;
+        admit(jobs.get_or_insert_with(HashMap::new), priority)
+    }
+
+    fn state(&self) -> (JobState, Option<String>) {
+        self.state
+            .lock()
+            .map(|s| s.clone())
+            .unwrap_or((JobState::Failed, Some("Lock poisoned".to_string())))
+    }
+
+    fn set_state(&self, state: JobState, error: Option<String>) {
+        if let Ok(mut current) = self.state.lock() {
+            // A finished job stays finished
+            if !current.0.is_finished() {
+                *current = (state, error);
+            }
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Raise the progress to `percent`, returning false if it was already there
+    pub fn advance(&self, percent: u32) -> bool {
+        self.percent.fetch_max(percent, Ordering::Relaxed) < percent
+    }
+
+    /// Run `f` on the inference worker and wait for its result
+    pub fn run<T, F>(self: &Arc<Self>, f: F) -> Result<T, String>
+    where
+        T: Send + 'static,
+        F: FnOnce(&WhisperContext) -> Result<T, String> + Send + 'static,
+    {
+        let (tx, rx) = mpsc::channel();
+        let task: Task = Box::new(move |model| {
+            let result = match model {
+                Some(model) => f(model),
+                None => Err("Whisper model not loaded. Call load_whisper_model first.".to_string()),
+            };
+            let _ = tx.send(result);
+        });
+
+        {
+            let mut queue = QUEUE.lock().map_err(|_| "Lock poisoned")?;
+            let queue = queue.get_or_insert_with(Queue::default);
+            match self.priority {
+                Priority::Live => queue.live.push_back((self.clone(), task)),
+                Priority::Batch => queue.batch.push_back((self.clone(), task)),
+            }
+        }
+        QUEUE_READY.notify_one();
+
+        match rx.recv() {
+            Ok(result) => result,
+            Err(_) if self.is_cancelled() => Err("Transcription cancelled".to_string()),
+            Err(_) => Err("Transcription failed unexpectedly".to_string()),
+        }
+    }
+
+    /// Record the outcome of the job
+    pub fn finish<T>(&self, result: &Result<T, String>) {
+        match result {
+            Ok(_) => {
+                self.percent.store(100, Ordering::Relaxed);
+                self.set_state(JobState::Completed, None);
+            }
+            Err(_) if self.is_cancelled() => self.set_state(JobState::Cancelled, None),
+            Err(e) => self.set_state(JobState::Failed, Some(e.clone())),
+        }
+    }
+
+    fn status(&self) -> JobStatus {
+        let (state, error) = self.state();
+        let queue_position = match state {
+            JobState::Queued => QUEUE
+                .lock()
+                .ok()
+                .and_then(|q| q.as_ref().and_then(|q| q.position(self.id))),
+            _ => None,
+        };
+        JobStatus {
+            id: self.id,
+            priority: self.priority,
+            state,
+            percent: self.percent.load(Ordering::Relaxed),
+            queue_position,
+            error,
+        }
+    }
+}
+
+/// Add a job to `jobs`, forgetting the oldest finished ones to make room.
+/// Live dictation is never turned away for batch work.
+fn admit(jobs: &mut HashMap<u64, Arc<Job>>, priority: Priority) -> Result<Arc<Job>, String> {
+    if priority == Priority::Batch {
+        let waiting = jobs
+            .values()
+            .filter(|j| j.priority == Priority::Batch && !j.state().0.is_finished())
+            .count();
+        if waiting >= MAX_BATCH_JOBS {
+            return Err(format!(
+                "Transcription queue is full ({} jobs waiting), try again later",
+                waiting
+            ));
+        }
+    }
+
+    let mut finished: Vec<u64> = jobs
+        .values()
+        .filter(|j| j.state().0.is_finished())
+        .map(|j| j.id)
+        .collect();
+    if finished.len() >= MAX_FINISHED_JOBS {
+        finished.sort_unstable();
+        for id in &finished[..=finished.len() - MAX_FINISHED_JOBS] {
+            jobs.remove(id);
+        }
+    }
+
+    let job = Arc::new(Job {
+        id: NEXT_JOB_ID.fetch_add(1, Ordering::Relaxed),
+        priority,
+        state: Mutex::new((JobState::Queued, None)),
+        cancelled: AtomicBool::new(false),
+        percent: AtomicU32::new(0),
+    });
+    jobs.insert(job.id, job.clone());
+    Ok(job)
+}
+
+/// Run a single task as its own job. The job can be listed and cancelled
+/// while it waits or runs, but is not kept once it finishes.
+pub(super) fn run_once<T, F>(priority: Priority, f: F) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: FnOnce(&WhisperContext) -> Result<T, String> + Send + 'static,
+{
+    let job = Job::register(priority)?;
+    let result = job.run(f);
+    if let Ok(mut jobs) = JOBS.lock() {
+        if let Some(jobs) = jobs.as_mut() {
+            jobs.remove(&job.id);
+        }
+    }
+    result
+}
+
+fn find_job(job_id: u64) -> Result<Arc<Job>, String> {
+    JOBS.lock()
+        .map_err(|_| "Lock poisoned")?
+        .as_ref()
+        .and_then(|jobs| jobs.get(&job_id).cloned())
+        .ok_or_else(|| format!("Unknown transcription job {}", job_id))
+}
+
+/// State, progress and queue position of a transcription job
+#[command]
+pub fn get_transcription_job(job_id: u64) -> Result<JobStatus, String> {
+    Ok(find_job(job_id)?.status())
+}
+
+/// All queued, running and recently finished jobs, oldest first
+#[command]
+pub fn list_transcription_jobs() -> Result<Vec<JobStatus>, String> {
+    let jobs: Vec<Arc<Job>> = JOBS
+        .lock()
+        .map_err(|_| "Lock poisoned")?
+        .as_ref()
+        .map(|jobs| jobs.values().cloned().collect())
+        .unwrap_or_default();
+
+    let mut statuses: Vec<JobStatus> = jobs.iter().map(|job| job.status()).collect();
+    statuses.sort_by_key(|s| s.id);
+    Ok(statuses)
+}
+
+/// Cancel a queued or running job. Running inference stops at whisper.cpp's
+/// next abort check; file jobs end with `transcription-cancelled`.
+#[command]
+pub fn cancel_transcription(job_id: u64) -> Result<(), String> {
+    let job = find_job(job_id)?;
+    if job.state().0.is_finished() {
+        return Err(format!("Transcription job {} has already finished", job_id));
+    }
+    job.cancelled.store(true, Ordering::Relaxed);
+
+    // Drop queued work right away; dropping a task wakes its submitter
+    if let Some(queue) = QUEUE.lock().map_err(|_| "Lock poisoned")?.as_mut() {
+        queue.live.retain(|(job, _)| job.id != job_id);
+        queue.batch.retain(|(job, _)| job.id != job_id);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task() -> Task {
+        Box::new(|_| {})
+    }
+
+    #[test]
+    fn live_work_goes_first() {
+        let mut jobs = HashMap::new();
+        let batch = admit(&mut jobs, Priority::Batch).unwrap();
+        let live = admit(&mut jobs, Priority::Live).unwrap();
+        let mut queue = Queue::default();
+        queue.batch.push_back((batch.clone(), task()));
+        queue.batch.push_back((batch.clone(), task()));
+        queue.live.push_back((live.clone(), task()));
+
+        assert_eq!(queue.position(live.id), Some(0));
+        assert_eq!(queue.position(batch.id), Some(1));
+        let order: Vec<u64> = std::iter::from_fn(|| queue.pop().map(|(job, _)| job.id)).collect();
+        assert_eq!(order, [live.id, batch.id, batch.id]);
+    }
+
+    #[test]
+    fn only_batch_jobs_fill_the_queue() {
+        let mut jobs = HashMap::new();
+        for _ in 0..MAX_BATCH_JOBS {
+            admit(&mut jobs, Priority::Batch).unwrap();
+        }
+        assert!(admit(&mut jobs, Priority::Batch).is_err());
+        assert!(admit(&mut jobs, Priority::Live).is_ok());
+
+        // Finished jobs free their place
+        let done = jobs
+            .values()
+            .find(|j| j.priority == Priority::Batch)
+            .unwrap()
+            .clone();
+        done.finish(&Ok(()));
+        assert!(admit(&mut jobs, Priority::Batch).is_ok());
+    }
+
+    #[test]
+    fn evicts_oldest_finished_jobs() {
+        let mut jobs = HashMap::new();
+        let mut ids = Vec::new();
+        for _ in 0..MAX_FINISHED_JOBS + 4 {
+            let job = admit(&mut jobs, Priority::Batch).unwrap();
+            job.finish::<()>(&Err("failed".to_string()));
+            ids.push(job.id);
+        }
+        let running = admit(&mut jobs, Priority::Batch).unwrap();
+
+        let finished = jobs.values().filter(|j| j.state().0.is_finished()).count();
+        assert_eq!(finished, MAX_FINISHED_JOBS - 1);
+        assert!(jobs.contains_key(&running.id));
+        assert!(jobs.contains_key(ids.last().unwrap()));
+        assert!(!jobs.contains_key(&ids[4]));
+        assert!(jobs.contains_key(&ids[5]));
+    }
+}
@@ -21,6 +21,14 @@ interface TypeResult {
   method?: string;
   error?: string;
   message?: string;
+  delivered?: number;
+  latencyMs?: number;
+}
+
+interface TargetWindow {
+  class?: string;
+  process?: string;
+  title?: string;
 }
 
 // Check if running in Tauri - cached
@@ -33,6 +41,7 @@ interface TauriAPI {
   typeTextWithDelay: (text: string, delayMs?: number) => Promise<TypeResult>;
   pasteText: (text: string) => Promise<TypeResult>;
   typeToPreviousApp: (text: string, inputMethod: string, hideWidget: boolean, typingDelay?: number) => Promise<TypeResult>;
+  rememberFocusedWindow: () => Promise<TargetWindow | null>;
   copyToClipboard: (text: string) => Promise<{ success: boolean }>;
   readClipboard: () => Promise<string>;
   checkAccessibilityPermission: () => Promise<boolean>;
@@ -41,7 +50,9 @@ interface TauriAPI {
   setAlwaysOnTop: (value: boolean) => Promise<void>;
   getAlwaysOnTop: () => Promise<boolean>;
   onToggleDictation: (callback: () => void) => () => void;
+  onStartDictation: (callback: () => void) => () => void;
   onStopDictation: (callback: () => void) => () => void;
+  onShortcutAction: (callback: (action: string) => void) => () => void;
 }
 
 // Cached Tauri API
@@ -64,6 +75,7 @@ const getTauriAPI = async (): Promise<TauriAPI | null> => {
     pasteText: (text: string) => invoke<TypeResult>('paste_text', { text }),
     typeToPreviousApp: (text: string, inputMethod: string, hideWidget: boolean, typingDelay?: number) =>
       invoke<TypeResult>('type_to_previous_app', { text, inputMethod, hideWidget, typingDelay }),
+    rememberFocusedWindow: () => invoke<TargetWindow | null>('remember_focused_window'),
     copyToClipboard: async (text: string) => {
       await invoke<TypeResult>('copy_to_clipboard', { text });
       return { success: true };
@@ -80,10 +92,18 @@ const getTauriAPI = async (): Promise<TauriAPI | null> => {
       const unlisten = listen('toggle-dictation', () => callback());
       return () => { unlisten.then(fn => fn()); };
     },
+    onStartDictation: (callback: () => void) => {
+      const unlisten = listen('start-dictation', () => callback());
+      return () => { unlisten.then(fn => fn()); };
+    },
     onStopDictation: (callback: () => void) => {
       const unlisten = listen('stop-dictation', () => callback());
       return () => { unlisten.then(fn => fn()); };
     },
+    onShortcutAction: (callback: (action: string) => void) => {
+      const unlisten = listen<string>('shortcut-action', (event) => callback(event.payload));
+      return () => { unlisten.then(fn => fn()); };
+    },
   };
   
   return cachedTauriAPI;
@@ -147,6 +167,10 @@ export const WidgetView = () => {
   // Load Whisper (primary) and VOSK (preview) - load both in parallel for speed
   const voskLoadingRef = useRef(false);
   const audioStreamRef = useRef<MediaStream | null>(null);
+  // Push-to-talk state lives in refs: on a quick tap the shortcut is
+  // released before startRecording has finished and state has caught up
+  const pushToTalkHeldRef = useRef(false);
+  const pushToTalkStartingRef = useRef(false);
   
   useEffect(() => {
     let cancelled = false;
@@ -232,6 +256,11 @@ export const WidgetView = () => {
       return;
     }
 
+    // Remember where the text should go before anything can take focus
+    tauriAPIRef.current?.rememberFocusedWindow().catch((err) => {
+      console.warn('Failed to remember the focused window:', err);
+    });
+
     try {
       audioChunksRef.current = [];
       
@@ -442,22 +471,49 @@ export const WidgetView = () => {
     if (!isTauri) return;
 
     let unsubscribeToggle: (() => void) | undefined;
+    let unsubscribeStart: (() => void) | undefined;
     let unsubscribeStop: (() => void) | undefined;
+    let unsubscribeAction: (() => void) | undefined;
 
     getTauriAPI().then(api => {
       if (api) {
         unsubscribeToggle = api.onToggleDictation(toggleRecording);
+        // Push-to-talk
+        unsubscribeStart = api.onStartDictation(async () => {
+          pushToTalkHeldRef.current = true;
+          if (pushToTalkStartingRef.current || mediaRecorderRef.current?.state === 'recording') return;
+          pushToTalkStartingRef.current = true;
+          try {
+            await startRecording();
+          } finally {
+            pushToTalkStartingRef.current = false;
+          }
+          // Released while recording was starting
+          if (!pushToTalkHeldRef.current && mediaRecorderRef.current?.state === 'recording') {
+            stopRecording();
+          }
+        });
         unsubscribeStop = api.onStopDictation(() => {
-          if (isRecording) stopRecording();
+          pushToTalkHeldRef.current = false;
+          // A start still in progress stops once it is done
+          if (!pushToTalkStartingRef.current && mediaRecorderRef.current?.state === 'recording') {
+            stopRecording();
+          }
+        });
+        unsubscribeAction = api.onShortcutAction((action) => {
+          if (action === 'delete') handleDelete();
+          else if (action === 'replace') handleReplace();
         });
       }
     });
 
     return () => {
       unsubscribeToggle?.();
+      unsubscribeStart?.();
       unsubscribeStop?.();
+      unsubscribeAction?.();
     };
-  }, [toggleRecording, isRecording]);
+  }, [toggleRecording, handleDelete, handleReplace]);
 
   // Swipe gesture handlers for compact mode
   const { handlers: swipeHandlers } = useSwipeGesture({